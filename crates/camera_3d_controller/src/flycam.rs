@@ -3,20 +3,24 @@ use bevy::{
     math::vec2,
     prelude::{
         Component, EulerRot, EventReader, Input, KeyCode, MouseButton, Quat, Query, Reflect, Res,
-        Time, Transform, Vec2, Vec3,
+        Time, Transform, Update, Vec2, Vec3,
     },
 };
 use bevy_denshi_ika_gen_plugin::gen_plugin;
 
 gen_plugin! {
-    pub(super) FlyCameraControllerPlugin;
+    pub FlyCameraControllerPlugin;
+    toggle(enabled = true);
+    #[cfg(feature = "profile")]
+    profile;
     reflect(
-        FlyCameraController,
-        FlyCameraInputs,
+        FlyCameraController: Component + Default,
+        FlyCameraInputs: Default,
         FlyCameraInput,
         FlyCameraAction
     );
     systems(Update)(fly_camera_controller);
+    self_test(frames = 10)(bevy::input::InputPlugin);
 }
 
 fn fly_camera_controller(
@@ -111,6 +115,16 @@ pub struct FlyCameraController {
     pub inputs: FlyCameraInputs,
 }
 
+/// A speed of 1 unit per second with the default [`FlyCameraInputs`]
+impl Default for FlyCameraController {
+    fn default() -> Self {
+        Self {
+            speed: 1.0,
+            inputs: FlyCameraInputs::default(),
+        }
+    }
+}
+
 #[derive(Reflect, Clone, Debug)]
 pub struct FlyCameraInputs(pub Vec<(Vec<FlyCameraInput>, FlyCameraAction)>);
 
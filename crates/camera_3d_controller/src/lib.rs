@@ -1,10 +1,10 @@
-use bevy_denshi_ika_gen_plugin::gen_plugin;
+use bevy_denshi_ika_gen_plugin::gen_plugin_group;
 
 use crate::flycam::FlyCameraControllerPlugin;
 
 pub mod flycam;
 
-gen_plugin! {
-    pub Camera3dControllerPlugin;
+gen_plugin_group! {
+    pub Camera3dControllerPlugins;
     plugins(FlyCameraControllerPlugin);
 }
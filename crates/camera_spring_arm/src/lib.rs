@@ -1,33 +1,61 @@
 use bevy::{
+    app::PostUpdate,
     core_pipeline::core_3d::Camera3dBundle,
     ecs::{
         bundle::Bundle,
         component::Component,
         query::{Changed, With},
+        schedule::IntoSystemConfigs,
         system::{Local, Query},
     },
     gizmos::gizmos::Gizmos,
     math::Vec3,
     reflect::Reflect,
     render::color::Color,
-    transform::components::Transform,
+    transform::{components::Transform, TransformSystem},
 };
 use bevy_denshi_ika_gen_plugin::gen_plugin;
 use bevy_xpbd_3d::{
     components::{Collider, Position, Rotation},
-    plugins::spatial_query::{ShapeCaster, ShapeHits, SpatialQueryFilter},
+    plugins::spatial_query::{ShapeCaster, ShapeHits, SpatialQueryFilter, SpatialQueryPlugin},
+    PhysicsSet,
 };
 
 gen_plugin! {
     pub CameraSpringArmPlugin;
-    reflect(CameraSpringArm);
-    systems(PostUpdate)((
+    toggle(enabled = true);
+    #[cfg(feature = "profile")]
+    profile;
+    requires(SpatialQueryPlugin);
+    reflect(CameraSpringArm: Component + Default);
+    // The shape caster is read by the physics and its hits are written in `PhysicsSet::Sync`,
+    // the camera transform is then propagated in the same frame
+    systems(
+        PostUpdate,
+        after = PhysicsSet::Sync,
+        before = TransformSystem::TransformPropagate,
+    )((
         update_camera_spring_arm_shape_raycaster,
         update_camera_spring_arm_shape_raycaster,
         update_camera_spring_arm_shape_caster_transform,
         update_camera_spring_arm,
         update_camera_spring_arm_shape_caster_transform,
     ).chain());
+    // `RenderPlugin` cannot run headlessly, `GizmoPlugin` and `PhysicsPlugins` only
+    // need its assets
+    self_test(
+        frames = 10,
+        setup = |app: &mut bevy::app::App| {
+            use bevy::asset::AssetApp;
+            app.add_plugins(bevy::asset::AssetPlugin::default())
+                .init_asset::<bevy::render::render_resource::Shader>()
+                .init_asset::<bevy::render::mesh::Mesh>();
+        },
+    )(
+        bevy::scene::ScenePlugin,
+        bevy::gizmos::GizmoPlugin,
+        bevy_xpbd_3d::plugins::PhysicsPlugins::default(),
+    );
 }
 
 #[derive(Bundle)]
@@ -52,6 +80,22 @@ pub struct CameraSpringArm {
     pub query_filter: SpatialQueryFilter,
 }
 
+/// An arm of 4 units with a camera radius of 1 unit, without rotation
+impl Default for CameraSpringArm {
+    fn default() -> Self {
+        Self {
+            distance: 4.0,
+            yaw: 0.0,
+            pitch: 0.0,
+            camera_yaw: 0.0,
+            camera_pitch: 0.0,
+            camera_roll: 0.0,
+            camera_radius: 1.0,
+            query_filter: SpatialQueryFilter::default(),
+        }
+    }
+}
+
 fn update_camera_spring_arm_shape_raycaster(
     mut cameras: Query<(&mut ShapeCaster, &CameraSpringArm), Changed<CameraSpringArm>>,
 ) {
@@ -63,6 +107,7 @@ fn update_camera_spring_arm_shape_raycaster(
     }
 }
 
+#[allow(clippy::type_complexity)]
 fn update_camera_spring_arm_shape_caster_transform(
     mut cameras: Query<(&mut ShapeCaster, &Transform), (With<CameraSpringArm>, Changed<Transform>)>,
     mut origin_direction: Local<(Vec3, Vec3)>,
@@ -0,0 +1,129 @@
+use std::{
+    env::consts::DLL_EXTENSION,
+    path::{Path, PathBuf},
+};
+
+use bevy_app::{App, Plugin};
+use bevy_dynamic_plugin::dynamically_load_plugin;
+use bevy_ecs::system::Resource;
+use bevy_log::{error, info, warn};
+
+/// Loads the plugins of the dynamic libraries (`.so`, `.dll` or `.dylib`) of a
+/// directory when it is built, the libraries export their plugin with the `dynamic`
+/// section of [`gen_plugin!`](crate::gen_plugin)
+///
+/// The libraries are never unloaded, the loaded plugins are listed in [`DynamicPlugins`]
+pub struct DynamicPluginsPlugin {
+    directory: PathBuf,
+}
+
+impl DynamicPluginsPlugin {
+    /// # Safety
+    ///
+    /// Same as [`dynamically_load_plugin`] for every library of the directory: they
+    /// must be linked against the exact same `libbevy_dylib` as the app (bevy's
+    /// `dynamic_linking` feature) and be built by the same compiler
+    pub unsafe fn new(directory: impl Into<PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+        }
+    }
+}
+
+impl Plugin for DynamicPluginsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DynamicPlugins>();
+
+        let entries = match std::fs::read_dir(&self.directory) {
+            Ok(entries) => entries,
+            Err(error) => {
+                warn!(
+                    "Cannot read the dynamic plugins directory `{}`: {error}",
+                    self.directory.display(),
+                );
+                return;
+            }
+        };
+        let mut paths = entries
+            .filter_map(|entry| Some(entry.ok()?.path()))
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|extension| extension == DLL_EXTENSION)
+            })
+            .collect::<Vec<_>>();
+        // Loaded in a stable order as the plugins may depend on each other
+        paths.sort();
+
+        for path in paths {
+            // SAFETY: upheld by the caller of `DynamicPluginsPlugin::new`
+            match unsafe { dynamically_load_plugin(&path) } {
+                Ok((library, plugin)) => {
+                    // Unloading the library would leave the app with dangling systems
+                    std::mem::forget(library);
+                    info!("Loaded `{}` from `{}`", plugin.name(), path.display());
+
+                    app.world
+                        .resource_mut::<DynamicPlugins>()
+                        .plugins
+                        .push(LoadedPlugin {
+                            name: plugin.name().to_string(),
+                            path,
+                        });
+                    app.add_plugins(DynamicPlugin(plugin));
+                }
+                Err(error) => error!("Cannot load `{}`: {error}", path.display()),
+            }
+        }
+    }
+}
+
+/// Plugins loaded by [`DynamicPluginsPlugin`], in the order they were added
+#[derive(Resource, Default, Debug)]
+pub struct DynamicPlugins {
+    plugins: Vec<LoadedPlugin>,
+}
+
+#[derive(Debug)]
+struct LoadedPlugin {
+    name: String,
+    path: PathBuf,
+}
+
+impl DynamicPlugins {
+    /// Names and library paths of the plugins
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Path)> {
+        self.plugins
+            .iter()
+            .map(|plugin| (plugin.name.as_str(), plugin.path.as_path()))
+    }
+}
+
+/// Adds a loaded plugin to the app like any other plugin so its `finish` and
+/// `cleanup` are called
+struct DynamicPlugin(Box<dyn Plugin>);
+
+impl Plugin for DynamicPlugin {
+    fn build(&self, app: &mut App) {
+        self.0.build(app);
+    }
+
+    fn ready(&self, app: &App) -> bool {
+        self.0.ready(app)
+    }
+
+    fn finish(&self, app: &mut App) {
+        self.0.finish(app);
+    }
+
+    fn cleanup(&self, app: &mut App) {
+        self.0.cleanup(app);
+    }
+
+    fn name(&self) -> &str {
+        self.0.name()
+    }
+
+    fn is_unique(&self) -> bool {
+        self.0.is_unique()
+    }
+}
@@ -0,0 +1,61 @@
+use bevy_ecs::{
+    event::{Event, EventReader},
+    system::{ParamSet, SystemParam, SystemParamItem},
+};
+use bevy_utils::all_tuples;
+
+/// Functions taking a reference to an event followed by system parameters,
+/// the handlers of the `on_event` sections of [`gen_plugin!`](crate::gen_plugin)
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` is not an event handler of `{E}`",
+    label = "the first parameter must be `&{E}`, followed by system parameters"
+)]
+pub trait EventHandler<E, Marker>: Send + Sync + 'static {
+    type Param: SystemParam;
+
+    fn handle(&mut self, event: &E, param: SystemParamItem<Self::Param>);
+}
+
+macro_rules! impl_event_handler {
+    ($($param: ident),*) => {
+        #[allow(non_snake_case)]
+        impl<E, Func, $($param: SystemParam),*> EventHandler<E, fn($($param,)*)> for Func
+        where
+            Func: Send + Sync + 'static,
+            for<'a> &'a mut Func:
+                FnMut(&E, $($param),*) + FnMut(&E, $(SystemParamItem<$param>),*),
+        {
+            type Param = ($($param,)*);
+
+            fn handle(&mut self, event: &E, param: SystemParamItem<($($param,)*)>) {
+                // Like `SystemParamFunction`, `rustc` needs this function
+                // to pick the implementation of `FnMut` to call
+                #[allow(clippy::too_many_arguments)]
+                fn call_inner<E, $($param,)*>(
+                    mut f: impl FnMut(&E, $($param,)*),
+                    event: &E,
+                    $($param: $param,)*
+                ) {
+                    f(event, $($param,)*)
+                }
+                let ($($param,)*) = param;
+                call_inner(self, event, $($param),*)
+            }
+        }
+    };
+}
+
+all_tuples!(impl_event_handler, 0, 15, P);
+
+/// System calling the handler for every event, the parameters of the
+/// handler are fetched again for each event
+#[doc(hidden)]
+pub fn handle_events<E: Event, Marker, H: EventHandler<E, Marker>>(
+    mut handler: H,
+) -> impl FnMut(EventReader<E>, ParamSet<(H::Param,)>) + Send + Sync + 'static {
+    move |mut events, mut param| {
+        for event in events.read() {
+            handler.handle(event, param.p0());
+        }
+    }
+}
@@ -0,0 +1,57 @@
+#[cfg(not(feature = "profile"))]
+use bevy_ecs::system::{IntoSystem, System};
+
+/// Expands to the statements of a `profile` section, fails on the name of the section
+/// without the `profile` feature
+#[cfg(feature = "profile")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __profile_section {
+    ($($tokens:tt)*) => { $($tokens)* };
+}
+
+#[cfg(not(feature = "profile"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __profile_section {
+    ($($tokens:tt)*) => {
+        ::core::compile_error!(
+            "the `profile` section needs the `profile` feature of `bevy_denshi_ika_gen_plugin`"
+        );
+    };
+}
+
+/// Expands to the statements of a `settings` section, fails on the name of the section
+/// without the `settings` feature, `@finish` marks the statements of
+/// [`Plugin::finish`](bevy_app::Plugin::finish) so the error is only reported once
+#[cfg(feature = "settings")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __settings_section {
+    (@finish $($tokens:tt)*) => { $($tokens)* };
+    ($($tokens:tt)*) => { $($tokens)* };
+}
+
+#[cfg(not(feature = "settings"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __settings_section {
+    (@finish $($tokens:tt)*) => {};
+    ($($tokens:tt)*) => {
+        ::core::compile_error!(
+            "the `settings` section needs the `settings` feature of `bevy_denshi_ika_gen_plugin`"
+        );
+    };
+}
+
+/// The systems of a plugin whose `profile` section is disabled by a `cfg` are still
+/// wrapped, they are left as is without the `profile` feature
+#[cfg(not(feature = "profile"))]
+#[doc(hidden)]
+pub fn profile_system<In, Out, Marker>(
+    system: impl IntoSystem<In, Out, Marker>,
+    _plugin: &'static str,
+    _enabled: bool,
+) -> impl System<In = In, Out = Out> {
+    IntoSystem::into_system(system)
+}
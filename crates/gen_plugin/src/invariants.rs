@@ -0,0 +1,181 @@
+use bevy_core::DebugNameItem;
+use bevy_ecs::{
+    entity::Entity,
+    event::{Event, EventWriter},
+};
+use bevy_log::error;
+use bevy_utils::{EntityHashMap, EntityHashSet};
+
+/// How the violations of the `invariants` sections of [`gen_plugin!`](crate::gen_plugin)
+/// are reported, each violation is reported once until it is fixed
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum InvariantSeverity {
+    /// Logs an error
+    #[default]
+    Log,
+    /// Panics, meant for tests
+    Panic,
+    /// Sends an [`InvariantViolated`] event
+    Event,
+}
+
+/// Sent when an entity violates an invariant with [`InvariantSeverity::Event`]
+#[derive(Event, Clone, Debug)]
+pub struct InvariantViolated {
+    pub entity: Entity,
+    /// Type name of the plugin declaring the invariant
+    pub plugin: &'static str,
+    /// Description of the invariant, e.g. "have `Transform`"
+    pub invariant: &'static str,
+}
+
+/// State of an invariant system, decides which entities to check
+/// and which violations to report
+#[doc(hidden)]
+#[derive(Default)]
+pub struct InvariantTracker {
+    /// Entities matched by the query on the last run
+    known: EntityHashSet<Entity>,
+    /// Entities matched by the query on the current run
+    current: EntityHashSet<Entity>,
+    /// Entities which had a watched component removed since the last run
+    removed: EntityHashSet<Entity>,
+    /// Indices of the invariants currently violated by each entity
+    violations: EntityHashMap<Entity, Vec<usize>>,
+}
+
+impl InvariantTracker {
+    pub fn removed(&mut self, entities: impl IntoIterator<Item = Entity>) {
+        self.removed.extend(entities);
+    }
+
+    /// Whether the invariants of the entity need to be checked, true if a watched component
+    /// was added, changed or removed, if the entity is new or if it is violating an invariant
+    pub fn should_check(&mut self, entity: Entity, changed: bool) -> bool {
+        self.current.insert(entity);
+
+        changed
+            || !self.known.contains(&entity)
+            || self.removed.contains(&entity)
+            || self.violations.contains_key(&entity)
+    }
+
+    /// Records the result of a check, returns whether the violation has to be reported
+    pub fn record(&mut self, entity: Entity, index: usize, valid: bool) -> bool {
+        let violations = self.violations.entry(entity).or_default();
+        let violated = violations.contains(&index);
+
+        match valid {
+            true => violations.retain(|violation| *violation != index),
+            false if !violated => violations.push(index),
+            false => {}
+        }
+        if violations.is_empty() {
+            self.violations.remove(&entity);
+        }
+
+        !valid && !violated
+    }
+
+    pub fn finish(&mut self) {
+        std::mem::swap(&mut self.known, &mut self.current);
+        self.current.clear();
+        self.removed.clear();
+
+        let known = &self.known;
+        self.violations.retain(|entity, _| known.contains(entity));
+    }
+}
+
+#[doc(hidden)]
+pub fn report_invariant(
+    severity: InvariantSeverity,
+    violation: InvariantViolated,
+    debug_name: &DebugNameItem,
+    events: &mut EventWriter<InvariantViolated>,
+) {
+    let InvariantViolated {
+        plugin, invariant, ..
+    } = violation;
+
+    match severity {
+        InvariantSeverity::Log => {
+            error!("Invalid entity {debug_name:?}, `{plugin}` expects it to {invariant}")
+        }
+        InvariantSeverity::Panic => {
+            panic!("Invalid entity {debug_name:?}, `{plugin}` expects it to {invariant}")
+        }
+        InvariantSeverity::Event => events.send(violation),
+    }
+}
+
+/// Calls the predicate of a `check` invariant, gives the type of its parameter
+#[doc(hidden)]
+pub fn check_invariant<T>(component: &T, predicate: impl FnOnce(&T) -> bool) -> bool {
+    predicate(component)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Runs the tracker over the entities with whether they changed and are valid,
+    /// returns the entities whose violation is reported
+    fn run(tracker: &mut InvariantTracker, entities: &[(Entity, bool, bool)]) -> Vec<Entity> {
+        let mut reported = Vec::new();
+        for &(entity, changed, valid) in entities {
+            if tracker.should_check(entity, changed) && tracker.record(entity, 0, valid) {
+                reported.push(entity);
+            }
+        }
+        tracker.finish();
+        reported
+    }
+
+    #[test]
+    fn reported_once() {
+        let entity = Entity::from_raw(0);
+        let mut tracker = InvariantTracker::default();
+
+        assert_eq!(run(&mut tracker, &[(entity, false, false)]), [entity]);
+        assert!(run(&mut tracker, &[(entity, false, false)]).is_empty());
+        assert!(run(&mut tracker, &[(entity, true, false)]).is_empty());
+    }
+
+    #[test]
+    fn reported_again_after_fix() {
+        let entity = Entity::from_raw(0);
+        let mut tracker = InvariantTracker::default();
+
+        assert_eq!(run(&mut tracker, &[(entity, false, false)]), [entity]);
+        assert!(run(&mut tracker, &[(entity, true, true)]).is_empty());
+        // Valid entities are only checked again when they change
+        assert!(!tracker.should_check(entity, false));
+        tracker.finish();
+        assert_eq!(run(&mut tracker, &[(entity, true, false)]), [entity]);
+    }
+
+    #[test]
+    fn checked_on_removal() {
+        let entity = Entity::from_raw(0);
+        let mut tracker = InvariantTracker::default();
+
+        assert!(run(&mut tracker, &[(entity, false, true)]).is_empty());
+        assert!(!tracker.should_check(entity, false));
+        tracker.finish();
+
+        tracker.removed([entity]);
+        assert_eq!(run(&mut tracker, &[(entity, false, false)]), [entity]);
+    }
+
+    #[test]
+    fn reported_again_after_reentry() {
+        let entity = Entity::from_raw(0);
+        let mut tracker = InvariantTracker::default();
+
+        assert_eq!(run(&mut tracker, &[(entity, false, false)]), [entity]);
+        // The entity stops matching the filter
+        assert!(run(&mut tracker, &[]).is_empty());
+        assert_eq!(run(&mut tracker, &[(entity, false, false)]), [entity]);
+    }
+}
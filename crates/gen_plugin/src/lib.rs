@@ -1,34 +1,226 @@
+use bevy_ecs::schedule::SystemSet;
+
+#[cfg(feature = "dynamic")]
+pub use crate::dynamic_plugins::{DynamicPlugins, DynamicPluginsPlugin};
+pub use crate::event_handler::EventHandler;
+pub use crate::invariants::{InvariantSeverity, InvariantViolated};
+pub use crate::main_app::MainExitCode;
+pub use crate::manifest::{GeneratedPlugin, GeneratedPlugins, PluginManifest, SystemsManifest};
+pub use crate::plugin_enabled::PluginEnabled;
+#[cfg(feature = "profile")]
+pub use crate::profile::{PluginTiming, PluginTimings, Timing};
+pub use crate::schedule_graph::{write_schedule_graphs, ScheduleGraphFormat};
+pub use crate::state_scoped::StateScoped;
+
+#[cfg(feature = "dynamic")]
+mod dynamic_plugins;
+mod event_handler;
+mod features;
+mod invariants;
+mod main_app;
+mod manifest;
+mod plugin_enabled;
+#[cfg(feature = "profile")]
+mod profile;
+mod schedule_graph;
+mod self_test;
+#[cfg(feature = "settings")]
+mod settings;
+mod spawn;
+mod state_resources;
+mod state_scoped;
+
 /// Generates an unit `struct` and implement [`Plugin`] for it
 ///
-/// [`bevy`] module needs to be in scope of the invocation
+/// The expansion only refers to bevy through this crate, the items used in the
+/// sections need to be in scope of the invocation
 ///
 /// Using main as the plugin name will result in the creation
-/// of the main function running the plugin
+/// of the main function running the plugin, the process exits with the
+/// [`MainExitCode`] of the app. Options can follow in parentheses:
+/// `headless` runs the app with [`ScheduleRunnerPlugin`] instead of the runner set by
+/// the plugins (which must not need a window), `frames = 600` exits after 600 frames,
+/// `args = parse_args` inserts the resource returned by `parse_args(std::env::args())`
+/// before the plugin is built and `app = make_app` creates the app with `make_app()`
+///
+/// Generic parameters can follow the plugin name, the struct then holds a
+/// [`PhantomData`](std::marker::PhantomData) of them and implements [`Default`],
+/// the parameters can be used in every section
+///
+/// Fields can be declared between braces after the plugin name, each field can
+/// have a default value (otherwise [`Default::default`] is used), a [`Default`]
+/// implementation and a builder-style setter per field are generated.
+/// `self` is available in every section
+///
+/// `impl for MyPlugin;` implements [`Plugin`] for a struct defined outside of the
+/// invocation (with its own derives, docs and methods), generic parameters can follow
+/// the name like in the other header. `self_test` and `dynamic` build it with
+/// [`Default`], [`GenPlugin`] is the same as a derive
+///
+/// Every system added by the plugin is in a generated [`SystemSet`] named after the
+/// plugin (e.g. `CameraSpringArmPluginSet`), available through [`PluginSystemSet`],
+/// `before` and `after` order it against the sets of other generated plugins in
+/// every schedule the plugin adds systems to, [`write_schedule_graphs`] groups the
+/// systems of the schedules by these sets
+///
+/// The rate of `fixed` sections is shared by every plugin, the first plugin built
+/// sets it and conflicting rates of later plugins are warned about
+///
+/// `requires` and `conflicts` are checked in [`Plugin::ready`] (the plugin registry
+/// is not accessible from [`Plugin::finish`]), once every plugin has been added,
+/// the plugins of a [`PluginGroup`] have to be named individually
+///
+/// `invariants` checks the entities matching its filter when they start matching it,
+/// when one of the components used by the invariants is added, changed or removed
+/// and while they violate an invariant, each violation is reported once until it is
+/// fixed with an [`InvariantSeverity`] (logged by default). The components of the
+/// parent and children are only checked when the `Parent` or `Children` of the
+/// entity change. `check` only applies to the entities having the component,
+/// `test_has(filter, (A, B))` is the same as `invariants(filter)(has(A, B))`
+///
+/// `self_test` generates a `#[cfg(test)]` test adding the plugin (with its default
+/// fields) and the listed plugins to a headless app with the plugins of
+/// `MinimalPlugins`. It fails if a system of the plugin is ambiguous with another
+/// system, if an update panics or if an invariant is violated. `setup = prepare` calls
+/// `prepare(&mut app)` before the plugins are added (e.g. to register the assets of a
+/// plugin needing a GPU)
+///
+/// `when` blocks group sections under conditions: each `cfg(...)` is added as an
+/// attribute of every section of the block and each `run_if = condition` applies
+/// to every system added by the block, blocks can be nested
+///
+/// `spawn` spawns its entities from a system named `<Plugin>::spawn` in the schedule (or
+/// in [`OnEnter`] with `on_enter = MyState::Variant`), the entities are spawned by a
+/// command so they exist after the commands of the schedule are applied, and the
+/// components are built each time the system runs. An entity is an optional [`Name`]
+/// followed by its components in parentheses and its children in `children[...]`,
+/// `asset(...)` in a component adds its argument to the [`Assets`] of the expected
+/// handle (e.g. `asset(Color::RED)` for a `Handle<StandardMaterial>`)
+///
+/// `settings` loads each resource from a RON file through the [`AssetServer`], the
+/// files need the `.settings.ron` extension (e.g. `settings/camera.settings.ron`) so
+/// the loader of the section does not take over the other `.ron` assets of the app,
+/// a literal path with another extension does not compile
+///
+/// `toggle` inserts a [`PluginEnabled`] resource of the plugin (unless the app already
+/// has one) and runs the set of the plugin in every schedule only while it is enabled,
+/// the resource is reflected and its state is logged in [`Plugin::finish`]
+///
+/// `profile` runs every system added by the plugin in a `plugin_system` tracing span
+/// named `<Plugin>::<system>` and collects the time spent per frame by the plugin and
+/// by each of its systems in the [`PluginTimings`] resource, also measured in the
+/// `DiagnosticsStore` of the app if it has one. The systems of tuples and of
+/// [`IntoSystemConfigs`] methods (e.g. `(a, b).chain()`) are profiled individually,
+/// the profiled systems keep their name so they can still be ordered against. It needs
+/// the `profile` feature, put it under a `cfg` to profile only some builds
+///
+/// `dynamic` exports the plugin (with its default fields) from the dynamic library of the
+/// crate (`crate-type = ["cdylib"]`) so it can be loaded by [`DynamicPluginsPlugin`] (with
+/// the `dynamic` feature), a library can only export one plugin
+///
+/// Every plugin implements [`GeneratedPlugin`] with a [`PluginManifest`] of its
+/// sections (the type names of their items, the expressions are not evaluated and
+/// sections disabled by `cfg` are left out), the manifests of the plugins built by
+/// the app are collected in the [`GeneratedPlugins`] resource
+///
+/// Unknown sections, duplicated `runner`, `name`, `is_unique`, `toggle`, `self_test`,
+/// `profile` and `dynamic` sections and malformed arguments are reported on the offending tokens,
+/// these sections can be repeated under different `cfg` (e.g. a `runner` per platform)
 ///
 /// # Examples
-/// ```rust
-/// gen_plugins! {
+/// ```rust,ignore
+/// gen_plugin! {
 ///     pub(super) MySupberbPlugin;
 ///     /// [`App::add_plugins`]
 ///     plugins(SomeChildPlugin, SomeOtherChildPlugin);
-///     /// [`App::register_type`]
-///     reflect(SomeReflectType, SomeOtherReflectType);
+///     /// [`App::register_type`], [`App::register_type_data`] of the traits following
+///     /// the type (e.g. `ReflectComponent` for `Component`), the traits are checked
+///     reflect(SomeReflectType, SomeComponent: Component + Default, SomeOtherType: Serialize);
 ///     /// [`App::add_systems`]
 ///     systems(Startup)(startup_system);
 ///     /// [`App::add_systems`] [`run_if`], [`in_state`]
 ///     systems(Update, SomeStates::SomeSate)(some_state_update);
+///     /// [`App::add_systems`] [`IntoSystemConfigs`], applied in order
+///     systems(
+///         Update,
+///         run_if = resource_exists::<SomeResource>(),
+///         distributive_run_if = some_condition,
+///         in_state = SomeStates::SomeState,
+///         in_set = SomeSet,
+///         before = some_system,
+///         after = SomeOtherSet,
+///         ambiguous_with = some_other_system,
+///         chain,
+///         ambiguous_with_all,
+///     )(first_system, second_system);
+///     /// [`App::is_plugin_added`] in [`Plugin::ready`], panics if one is missing
+///     requires(SomeRequiredPlugin, SomeOtherRequiredPlugin);
+///     /// [`App::is_plugin_added`] in [`Plugin::ready`], panics if one is added
+///     conflicts(SomeConflictingPlugin);
+///     /// [`Plugin::name`]
+///     name("my_superb_plugin");
+///     /// [`Plugin::is_unique`]
+///     is_unique(false);
+///     /// Runs the systems of the plugin while its [`PluginEnabled`] resource is enabled,
+///     /// with the initial state
+///     toggle(enabled = true);
 ///     /// [`App::init_resource`]
 ///     init_resources(SomeResource, SomeOtherResource);
 ///     /// [`App::init_non_send_resource`]
 ///     init_non_send_resources(SomeNonSendResource, SomeOtherNonSendResource);
 ///     /// [`App::init_schedules`]
 ///     init_schedules(SomeScheduleLabel, SomeOtherScheduleLabel);
-///     /// [`App::add_state`]
-///     states(SomeState, SomeOtherState);
+///     /// [`App::add_systems`] [`FixedUpdate`], sets the timestep of [`Time<Fixed>`]
+///     /// (the rate is anything `Into<f64>`, e.g. `hz = 60`), accepts the same
+///     /// configurations as `systems`
+///     fixed(hz = 60.0, chain)(physics_system, other_physics_system);
+///     /// [`App::add_event`], [`App::add_systems`] calling the handlers for every event,
+///     /// accepts the same configurations as `systems` and `if_present` to only run
+///     /// the handlers when there are events ([`on_event`] condition)
+///     on_event(SomeEvent, Update, if_present)(|event: &SomeEvent, mut commands: Commands| {});
+///     /// [`App::add_systems`] [`OnEnter`]
+///     on_enter(SomeState::SomeVariant)(enter_system);
+///     /// [`App::add_systems`] [`OnExit`]
+///     on_exit(SomeState::SomeVariant)(exit_system);
+///     /// [`App::add_systems`] [`OnTransition`]
+///     on_transition(SomeState::SomeVariant, SomeState::SomeOtherVariant)(transition_system);
+///     /// [`App::configure_sets`] [`IntoSystemSetConfigs::before`] of the plugin sets
+///     before(SomeOtherGeneratedPlugin);
+///     /// [`App::configure_sets`] [`IntoSystemSetConfigs::after`] of the plugin sets
+///     after(SomeOtherGeneratedPlugin);
+///     /// [`App::configure_sets`], the sets are sub-sets of the plugin set
+///     configure_sets(Update)((SomeSet::First, SomeSet::Second).chain());
+///     /// [`App::add_state`], the initial value is optional
+///     states(SomeState = SomeState::SomeVariant, SomeOtherState);
+///     /// [`App::add_systems`] [`OnExit`] despawning the entities with a [`StateScoped`]
+///     /// of the state, with their descendants
+///     state_scoped(SomeState::SomeVariant, SomeOtherState::SomeVariant);
+///     /// [`App::add_systems`] [`OnEnter`] initializing the resources, removed on [`OnExit`],
+///     /// the other systems of the plugin run after the initialization and before the removal
+///     state_resources(SomeState::SomeVariant)(SomeLevelResource, SomeOtherLevelResource);
+///     /// [`App::add_systems`] spawning the entities, in a schedule or `on_enter = state`
+///     spawn(on_enter = SomeState::SomeVariant)(
+///         "Level root" (SpatialBundle::default(), StateScoped(SomeState::SomeVariant)) children[
+///             "Player" (SomePlayerBundle::default()),
+///             (PbrBundle {
+///                 mesh: asset(shape::Cube::new(1.0)),
+///                 material: asset(Color::RED),
+///                 ..Default::default()
+///             }),
+///         ],
+///     );
 ///     /// [`App::add_event`]
-///     events(SomeEvent, SomeOtherEvent)
+///     events(SomeEvent, SomeOtherEvent);
 ///     /// [`App::insert_resource`]
 ///     resources(SomeResource::new(), SomeOtherResource::new());
+///     /// Resources loaded from `.settings.ron` files by the [`AssetServer`] (reloaded when the
+///     /// files change with the `file_watcher` feature), the default is used until the file is
+///     /// loaded and when it cannot be parsed, the files are loaded in [`Plugin::finish`] so the
+///     /// `AssetPlugin` can be added after the plugin, needs the `settings` feature
+///     settings(
+///         SomeSettings = "settings/some.settings.ron",
+///         SomeOtherSettings = "settings/other.settings.ron",
+///     );
 ///     /// [`App::insert_non_send_resource`]
 ///     non_send_resources(SomeNonSendResource::new(), SomeOtherNonSendResource::new());
 ///     /// [`App::set_runner`]
@@ -45,561 +237,210 @@
 ///     finish(|app| {});
 ///     /// [`Plugin::cleanup`]
 ///     cleanup(|app| {});
+///     /// Checks the entities matching the filter, the severity is optional
+///     #[cfg(feature = "dev")]
+///     invariants(With<Character>, severity = InvariantSeverity::Event)(
+///         has(CharacterController, CharacterSpeed),
+///         not_has(Camera),
+///         check(CharacterSpeed, |speed| speed.0 >= 0.0),
+///         parent(CharacterRoot),
+///         children(Collider),
+///     );
+///     /// Same as `invariants(With<Character>)(has(CharacterController, CharacterSpeed))`
 ///     #[cfg(feature = "dev")]
-///     test_has(Or<(With<Character>, With<CharacterController>, With<CharacterSpeed>)>, (Character, CharacterController, CharacterSpeed));
+///     test_has(With<Character>, (CharacterController, CharacterSpeed));
+///     /// Test running 10 updates, the plugins needed by the plugin can follow,
+///     /// the setup is optional
+///     self_test(frames = 10, setup = |app: &mut App| {})(SomeRequiredPlugin);
+///     /// Profiles the systems of the plugin, the only section without arguments
+///     profile;
+///     /// Entry point of the plugin in the dynamic library of the crate
+///     dynamic;
+///     /// Sections sharing conditions, `cfg` and `run_if` can be used alone
+///     when(cfg(feature = "dev"), run_if = debug_enabled) {
+///         reflect(SomeDebugType);
+///         systems(Update)(debug_system);
+///     }
+/// }
+///
+/// gen_plugin! {
+///     main(headless, frames = 600, args = SceneCheckArgs::from_args);
+///     plugins(SceneCheckPlugin);
+/// }
+///
+/// gen_plugin! {
+///     pub DespawnOnExitPlugin<S: States, C: Component> where S: Copy;
+///     systems(Update)(despawn_on_exit::<S, C>);
+/// }
+///
+/// #[derive(Clone, Debug, Default)]
+/// pub struct DebugOverlayPlugin {
+///     pub font_size: f32,
+/// }
+///
+/// gen_plugin! {
+///     impl for DebugOverlayPlugin;
+///     resources(DebugOverlayFontSize(self.font_size));
+///     systems(Update)(draw_debug_overlay);
+/// }
+///
+/// gen_plugin! {
+///     pub CameraSpringArmPlugin {
+///         debug_gizmos: bool = false,
+///         schedule: InternedScheduleLabel = PostUpdate.intern(),
+///     };
+///     resources(CameraSpringArmDebugGizmos(self.debug_gizmos));
+///     systems(self.schedule)(update_camera_spring_arm);
 /// }
 /// ```
 #[macro_export]
 macro_rules! gen_plugin {
-
-    {
-        main;
-        $($tail:tt)*
-    } => {
-
-        $crate::gen_plugin! {
-            @internal { {} {} {} { , ; app } } $($tail)*
-        }
-    };
-
-    {
-        $vis:vis $name:ident;
-        $($tail:tt)*
-    } => {
-
-        $crate::gen_plugin! {
-            @internal { {} {} {} { $vis , $name ; app } } $($tail)*
-        }
-    };
-
-    {
-        @internal
-        {
-            { $($build:tt)* } { $($finish:tt)* } { $($cleanup:tt)* }
-            { $vis:vis , $($name:ident)? ; $app:ident }
-        }
-        $(#[$attributes:meta])*
-        plugins($($plugins:expr),* $(,)?);
-        $($tail:tt)*
-    } => {
-        $crate::gen_plugin! {
-            @internal
-            {
-                {
-                    $($build)*
-                    $(#[$attributes])*
-                    $app.add_plugins(($($plugins),*));
-                }
-                { $($finish)* } { $($cleanup)* } { $vis , $($name)? ; $app }
-            }
-            $($tail)*
-        }
-    };
-
-    {
-        @internal
-        {
-            { $($build:tt)* } { $($finish:tt)* } { $($cleanup:tt)* }
-            { $vis:vis , $($name:ident)? ; $app:ident }
-        }
-        $(#[$attributes:meta])*
-        reflect($($types:ty),* $(,)?);
-        $($tail:tt)*
-    } => {
-        $crate::gen_plugin! {
-            @internal
-            {
-                {
-                    $($build)*
-                    $(#[$attributes])*
-                    {$($app.register_type::<$types>();)*}
-                }
-                { $($finish)* } { $($cleanup)* } { $vis , $($name)? ; $app }
-            }
-            $($tail)*
-        }
-    };
-
-
-    {
-        @internal
-        {
-            { $($build:tt)* } { $($finish:tt)* } { $($cleanup:tt)* }
-            { $vis:vis , $($name:ident)? ; $app:ident }
-        }
-        $(#[$attributes:meta])*
-        systems($schedule:expr $(, $state:expr)?)($($systems:expr),* $(,)?);
-        $($tail:tt)*
-    } => {
-        $crate::gen_plugin! {
-            @internal
-            {
-                {
-                    $($build)*
-                    $(#[$attributes])*
-                    $app.add_systems($schedule, ($($systems),*) $(.run_if(in_state($state)))?);
-                }
-                { $($finish)* } { $($cleanup)* } { $vis , $($name)? ; $app }
-            }
-            $($tail)*
-        }
-    };
-
-
-    {
-        @internal
-        {
-            { $($build:tt)* } { $($finish:tt)* } { $($cleanup:tt)* }
-            { $vis:vis , $($name:ident)? ; $app:ident }
-        }
-        $(#[$attributes:meta])*
-        init_resources($($resources:ty),* $(,)?);
-        $($tail:tt)*
-    } => {
-        $crate::gen_plugin! {
-            @internal
-            {
-                {
-                    $($build)*
-                    $(#[$attributes])*
-                    {$($app.init_resource::<$resources>();)*};
-                }
-                { $($finish)* } { $($cleanup)* } { $vis , $($name)? ; $app }
-            }
-            $($tail)*
-        }
-    };
-
-
-    {
-        @internal
-        {
-            { $($build:tt)* } { $($finish:tt)* } { $($cleanup:tt)* }
-            { $vis:vis , $($name:ident)? ; $app:ident }
-        }
-        $(#[$attributes:meta])*
-        init_non_send_resources($($resources:ty),* $(,)?);
-        $($tail:tt)*
-    } => {
-        $crate::gen_plugin! {
-            @internal
-            {
-                {
-                    $($build)*
-                    $(#[$attributes])*
-                    {$($app.init_non_send_resource::<$resources>();)*};
-                }
-                { $($finish)* } { $($cleanup)* } { $vis , $($name)? ; $app }
-            }
-            $($tail)*
-        }
-    };
-
-    {
-        @internal
-        {
-            { $($build:tt)* } { $($finish:tt)* } { $($cleanup:tt)* }
-            { $vis:vis , $($name:ident)? ; $app:ident }
-        }
-        $(#[$attributes:meta])*
-        init_schedules($($schedules:expr),* $(,)?);
-        $($tail:tt)*
-    } => {
-        $crate::gen_plugin! {
-            @internal
-            {
-                {
-                    $($build)*
-                    $(#[$attributes])*
-                    {$($app.init_schedule($schedules);)*};
-                }
-                { $($finish)* } { $($cleanup)* } { $vis , $($name)? ; $app }
-            }
-            $($tail)*
-        }
-    };
-
-    {
-        @internal
-        {
-            { $($build:tt)* } { $($finish:tt)* } { $($cleanup:tt)* }
-            { $vis:vis , $($name:ident)? ; $app:ident }
-        }
-        $(#[$attributes:meta])*
-        states($($states:ty),* $(,)?);
-        $($tail:tt)*
-    } => {
-        $crate::gen_plugin! {
-            @internal
-            {
-                {
-                    $($build)*
-                    $(#[$attributes])*
-                    {$($app.add_state::<$states>();)*};
-                }
-                { $($finish)* } { $($cleanup)* } { $vis , $($name)? ; $app }
-            }
-            $($tail)*
-        }
-    };
-
-    {
-        @internal
-        {
-            { $($build:tt)* } { $($finish:tt)* } { $($cleanup:tt)* }
-            { $vis:vis , $($name:ident)? ; $app:ident }
-        }
-        $(#[$attributes:meta])*
-        events($($events:ty),* $(,)?);
-        $($tail:tt)*
-    } => {
-        $crate::gen_plugin! {
-            @internal
-            {
-                {
-                    $($build)*
-                    $(#[$attributes])*
-                    {$($app.add_event::<$events>();)*};
-                }
-                { $($finish)* } { $($cleanup)* } { $vis , $($name)? ; $app }
-            }
-            $($tail)*
-        }
-    };
-
-    {
-        @internal
-        {
-            { $($build:tt)* } { $($finish:tt)* } { $($cleanup:tt)* }
-            { $vis:vis , $($name:ident)? ; $app:ident }
-        }
-        $(#[$attributes:meta])*
-        resources($($resources:expr),* $(,)?);
-        $($tail:tt)*
-    } => {
-        $crate::gen_plugin! {
-            @internal
-            {
-                {
-                    $($build)*
-                    $(#[$attributes])*
-                    {$($app.insert_resource($resources);)*};
-                }
-                { $($finish)* } { $($cleanup)* } { $vis , $($name)? ; $app }
-            }
-            $($tail)*
-        }
-    };
-
-    {
-        @internal
-        {
-            { $($build:tt)* } { $($finish:tt)* } { $($cleanup:tt)* }
-            { $vis:vis , $($name:ident)? ; $app:ident }
-        }
-        $(#[$attributes:meta])*
-        non_send_resources($($resources:expr),* $(,)?);
-        $($tail:tt)*
-    } => {
-        $crate::gen_plugin! {
-            @internal
-            {
-                {
-                    $($build)*
-                    $(#[$attributes])*
-                    {$($app.insert_non_send_resource($resources);)*};
-                }
-                { $($finish)* } { $($cleanup)* } { $vis , $($name)? ; $app }
-            }
-            $($tail)*
-        }
-    };
-
-    {
-        @internal
-        {
-            { $($build:tt)* } { $($finish:tt)* } { $($cleanup:tt)* }
-            { $vis:vis , $($name:ident)? ; $app:ident }
-        }
-        $(#[$attributes:meta])*
-        runner($runner:expr $(,)?);
-        $($tail:tt)*
-    } => {
-        $crate::gen_plugin! {
-            @internal
-            {
-                {
-                    $($build)*
-                    $(#[$attributes])*
-                    $app.set_runner($runner);
-                }
-                { $($finish)* } { $($cleanup)* } { $vis , $($name)? ; $app }
-            }
-            $($tail)*
-        }
-    };
-
-    {
-        @internal
-        {
-            { $($build:tt)* } { $($finish:tt)* } { $($cleanup:tt)* }
-            { $vis:vis , $($name:ident)? ; $app:ident }
-        }
-        $(#[$attributes:meta])*
-        assets($($assets:ty),* $(,)?);
-        $($tail:tt)*
-    } => {
-        $crate::gen_plugin! {
-            @internal
-            {
-                {
-                    $($build)*
-                    $(#[$attributes])*
-                    {$($app.init_asset::<$assets>();)*};
-                }
-                { $($finish)* } { $($cleanup)* } { $vis , $($name)? ; $app }
-            }
-            $($tail)*
-        }
-    };
-
-    {
-        @internal
-        {
-            { $($build:tt)* } { $($finish:tt)* } { $($cleanup:tt)* }
-            { $vis:vis , $($name:ident)? ; $app:ident }
-        }
-        $(#[$attributes:meta])*
-        asset_loaders($($asset_loaders:ty),* $(,)?);
-        $($tail:tt)*
-    } => {
-        $crate::gen_plugin! {
-            @internal
-            {
-                {
-                    $($build)*
-                    $(#[$attributes])*
-                    {$($app.init_asset_loader::<$asset_loaders>();)*};
-                }
-                { $($finish)* } { $($cleanup)* } { $vis , $($name)? ; $app }
-            }
-            $($tail)*
-        }
-    };
-
-    {
-        @internal
-        {
-            { $($build:tt)* } { $($finish:tt)* } { $($cleanup:tt)* }
-            { $vis:vis , $($name:ident)? ; $app:ident }
-        }
-        $(#[$attributes:meta])*
-        schedules($($schedules:expr),* $(,)?);
-        $($tail:tt)*
-    } => {
-        $crate::gen_plugin! {
-            @internal
-            {
-                {
-                    $($build)*
-                    $(#[$attributes])*
-                    {$($app.add_schedule($schedules);)*};
-                }
-                { $($finish)* } { $($cleanup)* } { $vis , $($name)? ; $app }
-            }
-            $($tail)*
-        }
-    };
-
-    {
-        @internal
-        {
-            { $($build:tt)* } { $($finish:tt)* } { $($cleanup:tt)* }
-            { $vis:vis , $($name:ident)? ; $app:ident }
-        }
-        $(#[$attributes:meta])*
-        build($func:expr);
-        $($tail:tt)*
-    } => {
-        $crate::gen_plugin! {
-            @internal
-            {
-                {
-                    $($build)*
-                    $(#[$attributes])*
-                    ($func)($app);
-                }
-                { $($finish)* } { $($cleanup)* } { $vis , $($name)? ; $app }
-            }
-            $($tail)*
-        }
+    ($($tokens:tt)*) => {
+        $crate::__private::gen_plugin! { $crate; $($tokens)* }
     };
+}
 
-    {
-        @internal
-        {
-            { $($build:tt)* } { $($finish:tt)* } { $($cleanup:tt)* }
-            { $vis:vis , $($name:ident)? ; $app:ident }
-        }
-        $(#[$attributes:meta])*
-        finish($func:expr);
-        $($tail:tt)*
-    } => {
-        $crate::gen_plugin! {
-            @internal
-            {
-                { $($build)* }
-                {
-                    $($finish)*
-                    $(#[$attributes])*
-                    ($func)($app);
-                }
-                { $($cleanup)* } { $vis , $($name)? ; $app }
-            }
-            $($tail)*
-        }
-    };
+/// Implements [`Plugin`] for the struct like the `impl for` header of [`gen_plugin!`],
+/// the sections are read from the `#[gen_plugin(...)]` attributes of the struct
+///
+/// The expansion refers to this crate as `::bevy_denshi_ika_gen_plugin`, a crate renaming
+/// the dependency gives its path with `#[gen_plugin(crate = ::my_gen_plugin)]`
+///
+/// # Examples
+/// ```rust,ignore
+/// /// Moves the spring arms, see [`CameraSpringArm`]
+/// #[derive(GenPlugin, Clone)]
+/// #[gen_plugin(
+///     reflect(CameraSpringArm: Component + Default);
+///     systems(self.schedule)(update_camera_spring_arm);
+/// )]
+/// #[gen_plugin(toggle(enabled = true);)]
+/// pub struct CameraSpringArmPlugin {
+///     pub schedule: InternedScheduleLabel,
+/// }
+///
+/// // Interned labels do not implement `Default`
+/// impl Default for CameraSpringArmPlugin {
+///     fn default() -> Self {
+///         Self {
+///             schedule: PostUpdate.intern(),
+///         }
+///     }
+/// }
+/// ```
+pub use bevy_denshi_ika_gen_plugin_macros::GenPlugin;
 
-    {
-        @internal
-        {
-            { $($build:tt)* } { $($finish:tt)* } { $($cleanup:tt)* }
-            { $vis:vis , $($name:ident)? ; $app:ident }
-        }
-        $(#[$attributes:meta])*
-        cleanup($func:expr);
-        $($tail:tt)*
-    } => {
-        $crate::gen_plugin! {
-            @internal
-            {
-                { $($build)* } { $($finish)* }
-                {
-                    $($cleanup)*
-                    $(#[$attributes])*
-                    ($func)($app);
-                }
-                { $vis , $($name)? ; $app }
-            }
-            $($tail)*
-        }
+/// Generates an unit `struct` and implement [`PluginGroup`] for it, the plugins of
+/// the group can be disabled or replaced individually through [`PluginGroupBuilder`]
+///
+/// The header is the same as [`gen_plugin!`] (except for `main`), generic
+/// parameters and fields are supported and `self` is available in every section
+///
+/// # Examples
+/// ```rust,ignore
+/// gen_plugin_group! {
+///     pub MySuperbPlugins;
+///     /// [`PluginGroupBuilder::add`]
+///     plugins(SomePlugin, SomeOtherPlugin::default());
+///     /// [`PluginGroupBuilder::add_before`], the plugins keep their order
+///     before(SomePlugin)(FirstPlugin, SecondPlugin);
+///     /// [`PluginGroupBuilder::add_after`], the plugins keep their order
+///     after(SomePlugin)(FirstPlugin, SecondPlugin);
+///     /// [`PluginGroupBuilder::set`], replaces a plugin added by a previous section
+///     set(SomePlugin::new(10));
+///     /// [`PluginGroupBuilder::disable`]
+///     disable(SomeOtherPlugin);
+/// }
+///
+/// App::new().add_plugins(MySuperbPlugins.build().disable::<SomePlugin>());
+/// ```
+#[macro_export]
+macro_rules! gen_plugin_group {
+    ($($tokens:tt)*) => {
+        $crate::__private::gen_plugin_group! { $crate; $($tokens)* }
     };
+}
 
-    {
-        @internal
-        {
-            { $($build:tt)* } { $($finish:tt)* } { $($cleanup:tt)* }
-            { $vis:vis , $($name:ident)? ; $app:ident }
-        }
-        $(#[$attributes:meta])*
-        test_has($filter:ty, ($($required:ty),+));
-        $($tail:tt)*
-    } => {
-        $crate::gen_plugin! {
-            @internal
-            {
-                {
-                    $($build)*
-                    $(#[$attributes])*
-                    $app.add_systems(
-                        Update,
-                        |query: Query<(DebugName, ($(Has<$required>),+)), $filter>| {
-                            for (debug_name, elements) in &query {
-                                let elements = Into::<[bool; $((1, std::marker::PhantomData::<$required>).0 +)+ 0]>::into(elements);
-
-                                if elements.into_iter().all(|has| has) {
-                                    continue;
-                                };
+/// Implemented by the plugins generated by [`gen_plugin!`], gives the
+/// [`SystemSet`] containing all of the systems of the plugin
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` is not a plugin generated by `gen_plugin!`",
+    label = "only plugins generated by `gen_plugin!` have a system set"
+)]
+pub trait PluginSystemSet {
+    type SystemSet: SystemSet + Default;
+}
 
-                                bevy::log::error!(
-                                    "{}",
-                                    format!(
-                                        "Invalid entity {:?} {}",
-                                        debug_name,
-                                        [$((stringify!($required))),*]
-                                            .iter()
-                                            .enumerate()
-                                            .map(|(i, ty)| format!("\n\t{}: {}", ty, elements[i]))
-                                            .collect::<Vec<String>>()
-                                            .join("")
-                                    )
-                                );
-                            }
-                        }
-                    );
-                }
-                { $($finish)* } { $($cleanup)* }
-                { $vis , $($name)? ; $app }
-            }
-            $($tail)*
-        }
+#[doc(hidden)]
+pub mod __private {
+    use bevy_app::App;
+    use bevy_ecs::{
+        schedule::{InternedScheduleLabel, ScheduleLabel},
+        system::Resource,
     };
-
-    {
-        @internal
-        {
-            { $($build:tt)* } { $($finish:tt)* } { $($cleanup:tt)* }
-            { $vis:vis , ; $app:ident }
-        }
-    } => {
-        fn main() {
-            struct MainPlugin;
-            $crate::gen_plugin! {
-                @internal_impl
-                {
-                    { $($build)* } { $($finish)* } { $($cleanup)* }
-                    { MainPlugin $app }
-                }
-            }
-
-            bevy::prelude::App::new().add_plugins(MainPlugin).run();
-        }
-
+    use bevy_log::warn;
+    use bevy_time::{Fixed, Time};
+
+    pub use bevy_app;
+    pub use bevy_asset;
+    pub use bevy_core;
+    pub use bevy_denshi_ika_gen_plugin_macros::{gen_plugin, gen_plugin_group};
+    pub use bevy_ecs;
+    pub use bevy_hierarchy;
+    pub use bevy_log;
+    pub use bevy_reflect;
+    pub use bevy_utils;
+    pub use serde;
+
+    pub use crate::event_handler::handle_events;
+    #[cfg(not(feature = "profile"))]
+    pub use crate::features::profile_system;
+    pub use crate::invariants::{check_invariant, report_invariant, InvariantTracker};
+    pub use crate::main_app::run_main;
+    pub use crate::manifest::{register_manifest, type_name_of_returned};
+    pub use crate::plugin_enabled::{init_plugin_enabled, log_plugin_enabled, plugin_enabled};
+    #[cfg(feature = "profile")]
+    pub use crate::profile::{profile_plugin, profile_system};
+    pub use crate::self_test::{run_self_test, self_test_app, SelfTest};
+    #[cfg(feature = "settings")]
+    pub use crate::settings::{apply_settings, load_settings};
+    pub use crate::spawn::{spawn_asset, spawn_system};
+    pub use crate::state_resources::{
+        init_state_resource, remove_state_resource, StateResourcesSet,
     };
-
-    {
-        @internal
-        {
-            { $($build:tt)* } { $($finish:tt)* } { $($cleanup:tt)* }
-            { $vis:vis , $name:ident ; $app:ident }
-        }
-    } => {
-        $vis struct $name;
-
-        $crate::gen_plugin! {
-            @internal_impl
-            {
-                { $($build)* } { $($finish)* } { $($cleanup)* }
-                { $name $app }
+    pub use crate::state_scoped::despawn_state_scoped;
+    pub use crate::{__profile_section as profile_section, __settings_section as settings_section};
+
+    /// Rate of [`Time<Fixed>`] asked by the first plugin with a `fixed` section
+    #[derive(Resource)]
+    struct FixedTimestep {
+        plugin: &'static str,
+        hz: f64,
+    }
+
+    /// Sets the timestep of [`Time<Fixed>`] for the `fixed` section of `plugin`,
+    /// the first rate asked is kept and conflicting ones are warned about
+    pub fn set_fixed_timestep(app: &mut App, plugin: &'static str, hz: f64) {
+        match app.world.get_resource::<FixedTimestep>() {
+            Some(timestep) if timestep.hz != hz => warn!(
+                "`{plugin}` asks for a fixed timestep of {hz} Hz \
+                but `{}` already asked for {} Hz, keeping {} Hz",
+                timestep.plugin, timestep.hz, timestep.hz,
+            ),
+            Some(_) => {}
+            None => {
+                app.insert_resource(FixedTimestep { plugin, hz });
+                app.insert_resource(Time::<Fixed>::from_hz(hz));
             }
         }
-    };
-
-    {
-        @internal_impl
-        {
-            { $($build:tt)* } { $($finish:tt)* } { $($cleanup:tt)* }
-            { $name:ident $app:ident }
-        }
-    } => {
-        impl bevy::prelude::Plugin for $name {
-            fn build(&self, $app: &mut bevy::prelude::App) {
-                use bevy::prelude::*;
-
-                $($build)*
-            }
-
-            fn finish(&self, $app: &mut bevy::prelude::App) {
-                use bevy::prelude::*;
+    }
 
-                $($finish)*
-            }
-
-            fn cleanup(&self, $app: &mut bevy::prelude::App) {
-                use bevy::prelude::*;
-
-                $($cleanup)*
-            }
+    /// Adds the schedule to the schedules the set of a plugin is configured in,
+    /// unless it was already added through another path
+    pub fn push_schedule(schedules: &mut Vec<InternedScheduleLabel>, schedule: impl ScheduleLabel) {
+        let schedule = schedule.intern();
+        if !schedules.contains(&schedule) {
+            schedules.push(schedule);
         }
-    };
+    }
 }
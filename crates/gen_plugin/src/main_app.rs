@@ -0,0 +1,72 @@
+use std::{
+    process::ExitCode,
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        Arc,
+    },
+};
+
+use bevy_app::{App, AppExit, Last, ScheduleRunnerPlugin};
+use bevy_ecs::{
+    event::{EventReader, EventWriter},
+    schedule::IntoSystemConfigs,
+    system::{Local, Res, Resource},
+};
+
+/// Exit code of the process running the `main` of [`gen_plugin!`](crate::gen_plugin),
+/// read when [`AppExit`] is sent (0 by default)
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct MainExitCode(pub u8);
+
+/// Number of frames after which the app exits, from the `frames` option of `main`
+#[derive(Resource)]
+struct ExitAfterFrames(u32);
+
+/// Gives the exit code to `run_main` as the runner consumes the app
+#[derive(Resource)]
+struct ExitCodeSender(Arc<AtomicU8>);
+
+/// Runs the app of `main`, returns the [`MainExitCode`] of the app once it exits
+#[doc(hidden)]
+pub fn run_main(mut app: App, headless: bool, frames: Option<u32>) -> ExitCode {
+    // Added after the main plugin so its runner replaces the one of the plugins
+    if headless && !app.is_plugin_added::<ScheduleRunnerPlugin>() {
+        app.add_plugins(ScheduleRunnerPlugin::default());
+    }
+
+    if let Some(frames) = frames {
+        app.insert_resource(ExitAfterFrames(frames));
+        app.add_systems(Last, exit_after_frames.before(send_exit_code));
+    }
+
+    let exit_code = Arc::new(AtomicU8::new(0));
+    app.add_event::<AppExit>();
+    app.init_resource::<MainExitCode>();
+    app.insert_resource(ExitCodeSender(exit_code.clone()));
+    app.add_systems(Last, send_exit_code);
+
+    app.run();
+
+    ExitCode::from(exit_code.load(Ordering::Relaxed))
+}
+
+fn exit_after_frames(
+    frames: Res<ExitAfterFrames>,
+    mut frame: Local<u32>,
+    mut exit: EventWriter<AppExit>,
+) {
+    *frame += 1;
+    if *frame >= frames.0 {
+        exit.send(AppExit);
+    }
+}
+
+fn send_exit_code(
+    mut exit: EventReader<AppExit>,
+    exit_code: Res<MainExitCode>,
+    sender: Res<ExitCodeSender>,
+) {
+    if exit.read().next().is_some() {
+        sender.0.store(exit_code.0, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,94 @@
+use bevy_app::App;
+use bevy_ecs::system::Resource;
+use serde::Serialize;
+
+/// Sections of a plugin generated by [`gen_plugin!`](crate::gen_plugin), the items are
+/// the type names of the plugins, systems and types of the sections (e.g. `my_crate::MyResource`)
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct PluginManifest {
+    pub name: &'static str,
+    pub plugins: Vec<&'static str>,
+    pub systems: Vec<SystemsManifest>,
+    pub reflect: Vec<&'static str>,
+    pub events: Vec<&'static str>,
+    pub resources: Vec<&'static str>,
+    pub states: Vec<&'static str>,
+}
+
+/// Systems added to a schedule by a section, the systems of tuples and of
+/// [`IntoSystemConfigs`](bevy_ecs::schedule::IntoSystemConfigs) methods (e.g.
+/// `(a, b).chain()`) are listed individually
+#[derive(Clone, Debug, Serialize)]
+pub struct SystemsManifest {
+    pub schedule: &'static str,
+    pub systems: Vec<&'static str>,
+}
+
+impl PluginManifest {
+    /// Whether one of the sections adds the system, from its name in the schedule
+    /// (e.g. `my_crate::my_system`). The handlers of `on_event` sections are named
+    /// after their own type rather than the system calling them
+    pub fn owns_system(&self, system: &str) -> bool {
+        self.systems
+            .iter()
+            .flat_map(|systems| &systems.systems)
+            .any(|name| *name == system)
+    }
+
+    /// Whether the type is reflected, an event, a resource or a state of the plugin,
+    /// from its type name (e.g. `my_crate::MyResource`)
+    pub fn owns_type(&self, type_name: &str) -> bool {
+        [&self.reflect, &self.events, &self.resources, &self.states]
+            .into_iter()
+            .flatten()
+            .any(|name| *name == type_name)
+    }
+}
+
+/// Implemented by the plugins generated by [`gen_plugin!`](crate::gen_plugin)
+pub trait GeneratedPlugin {
+    fn manifest(&self) -> PluginManifest;
+}
+
+/// Manifests of the generated plugins added to the app, in the order they were built
+#[derive(Resource, Default, Debug, Serialize)]
+pub struct GeneratedPlugins {
+    plugins: Vec<PluginManifest>,
+}
+
+impl GeneratedPlugins {
+    pub fn iter(&self) -> impl Iterator<Item = &PluginManifest> {
+        self.plugins.iter()
+    }
+
+    /// Plugin adding the system, see [`PluginManifest::owns_system`]
+    pub fn owner_of_system(&self, system: &str) -> Option<&PluginManifest> {
+        self.iter().find(|plugin| plugin.owns_system(system))
+    }
+
+    /// Plugin registering the type, see [`PluginManifest::owns_type`]
+    pub fn owner_of_type(&self, type_name: &str) -> Option<&PluginManifest> {
+        self.iter().find(|plugin| plugin.owns_type(type_name))
+    }
+
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(&self.plugins)
+            .expect("manifests only contain strings and are always serializable")
+    }
+}
+
+#[doc(hidden)]
+pub fn register_manifest(app: &mut App, manifest: PluginManifest) {
+    app.init_resource::<GeneratedPlugins>();
+    app.world
+        .resource_mut::<GeneratedPlugins>()
+        .plugins
+        .push(manifest);
+}
+
+/// Type name of the value returned by `value`, which is not called
+#[doc(hidden)]
+pub fn type_name_of_returned<T>(_value: impl FnOnce() -> T) -> &'static str {
+    std::any::type_name::<T>()
+}
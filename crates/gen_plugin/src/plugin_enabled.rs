@@ -0,0 +1,118 @@
+use std::{any::type_name, fmt, marker::PhantomData};
+
+use bevy_app::App;
+use bevy_ecs::{
+    reflect::ReflectResource,
+    system::{Res, Resource},
+};
+use bevy_log::info;
+use bevy_reflect::{
+    std_traits::ReflectDefault, utility::GenericTypePathCell, GetTypeRegistration, Reflect,
+    TypePath,
+};
+use bevy_utils::get_short_name;
+
+/// Runs the systems of the plugin `P` while `enabled` is true, inserted by the
+/// `toggle` section of [`gen_plugin!`](crate::gen_plugin) unless the app already has it
+#[derive(Resource, Reflect)]
+#[reflect(Resource, Default, type_path = false)]
+pub struct PluginEnabled<P: 'static> {
+    pub enabled: bool,
+    #[reflect(ignore)]
+    marker: PhantomData<fn() -> P>,
+}
+
+impl<P: 'static> PluginEnabled<P> {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<P: 'static> Default for PluginEnabled<P> {
+    fn default() -> Self {
+        Self::new(true)
+    }
+}
+
+impl<P: 'static> Clone for PluginEnabled<P> {
+    fn clone(&self) -> Self {
+        Self::new(self.enabled)
+    }
+}
+
+impl<P: 'static> fmt::Debug for PluginEnabled<P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct(&get_short_name(type_name::<P>()))
+            .field("enabled", &self.enabled)
+            .finish()
+    }
+}
+
+// The path is built from the `type_name` of the plugin, so the plugin itself does
+// not need to implement `TypePath`
+impl<P: 'static> TypePath for PluginEnabled<P> {
+    fn type_path() -> &'static str {
+        static CELL: GenericTypePathCell = GenericTypePathCell::new();
+        CELL.get_or_insert::<Self, _>(|| {
+            format!(
+                "{}::PluginEnabled<{}>",
+                Self::module_path().unwrap(),
+                type_name::<P>(),
+            )
+        })
+    }
+
+    fn short_type_path() -> &'static str {
+        static CELL: GenericTypePathCell = GenericTypePathCell::new();
+        CELL.get_or_insert::<Self, _>(|| {
+            format!("PluginEnabled<{}>", get_short_name(type_name::<P>()))
+        })
+    }
+
+    fn type_ident() -> Option<&'static str> {
+        Some("PluginEnabled")
+    }
+
+    fn crate_name() -> Option<&'static str> {
+        Some(module_path!().split("::").next().unwrap())
+    }
+
+    fn module_path() -> Option<&'static str> {
+        Self::crate_name()
+    }
+}
+
+/// Inserts the [`PluginEnabled`] of the plugin `P` if the app does not have it yet,
+/// so the app can choose the initial state before adding the plugin
+#[doc(hidden)]
+pub fn init_plugin_enabled<P: 'static>(app: &mut App, enabled: bool)
+where
+    PluginEnabled<P>: GetTypeRegistration,
+{
+    if !app.world.contains_resource::<PluginEnabled<P>>() {
+        app.insert_resource(PluginEnabled::<P>::new(enabled));
+    }
+    app.register_type::<PluginEnabled<P>>();
+}
+
+/// Logs the state of the plugin `P` once the app is built
+#[doc(hidden)]
+pub fn log_plugin_enabled<P: 'static>(app: &App, plugin: &str) {
+    let enabled = app
+        .world
+        .get_resource::<PluginEnabled<P>>()
+        .is_none_or(|enabled| enabled.enabled);
+    info!(
+        "`{plugin}` is {}",
+        if enabled { "enabled" } else { "disabled" },
+    );
+}
+
+/// Run condition of the sets of the plugin `P`, a missing [`PluginEnabled`] counts as enabled
+#[doc(hidden)]
+pub fn plugin_enabled<P: 'static>(enabled: Option<Res<PluginEnabled<P>>>) -> bool {
+    enabled.is_none_or(|enabled| enabled.enabled)
+}
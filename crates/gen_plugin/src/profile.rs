@@ -0,0 +1,360 @@
+use std::{
+    any::TypeId,
+    borrow::Cow,
+    collections::{hash_map::DefaultHasher, VecDeque},
+    hash::{Hash, Hasher},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use bevy_app::{App, First};
+use bevy_diagnostic::{
+    Diagnostic, DiagnosticId, DiagnosticMeasurement, DiagnosticsStore, MAX_DIAGNOSTIC_NAME_WIDTH,
+};
+use bevy_ecs::{
+    archetype::ArchetypeComponentId,
+    component::{ComponentId, Tick},
+    query::Access,
+    schedule::InternedSystemSet,
+    system::{IntoSystem, ResMut, Resource, System},
+    world::{unsafe_world_cell::UnsafeWorldCell, World},
+};
+use bevy_utils::{
+    get_short_name,
+    tracing::{info_span, Span},
+    Duration, Instant, Uuid,
+};
+
+/// Number of frames averaged by the [`Timing`]s of the [`PluginTimings`]
+const HISTORY_LENGTH: usize = 120;
+
+/// Time spent per frame by the systems of the plugins with a `profile` section of
+/// [`gen_plugin!`](crate::gen_plugin), the timings of a frame are collected in [`First`]
+/// of the next frame and are also measured in the [`DiagnosticsStore`] (in ms) if the
+/// app has one
+#[derive(Resource, Default, Debug)]
+pub struct PluginTimings {
+    plugins: Vec<PluginTiming>,
+}
+
+/// Timings of a plugin and of its systems, in the order the systems were initialized
+#[derive(Debug)]
+pub struct PluginTiming {
+    timing: Timing,
+    systems: Vec<SystemTiming>,
+}
+
+#[derive(Debug)]
+struct SystemTiming {
+    timing: Timing,
+    /// Nanoseconds spent in the system since the timings were last collected
+    elapsed: Arc<AtomicU64>,
+}
+
+/// Time spent per frame over the last frames
+#[derive(Debug)]
+pub struct Timing {
+    name: String,
+    diagnostic_id: DiagnosticId,
+    history: VecDeque<Duration>,
+}
+
+impl PluginTimings {
+    pub fn iter(&self) -> impl Iterator<Item = &PluginTiming> {
+        self.plugins.iter()
+    }
+
+    /// Timings of the plugin, from the short name of its type (e.g. `CameraSpringArmPlugin`)
+    pub fn get(&self, plugin: &str) -> Option<&PluginTiming> {
+        self.plugins
+            .iter()
+            .find(|timing| timing.timing.name == plugin)
+    }
+
+    fn register_plugin(&mut self, plugin: &str) -> &mut PluginTiming {
+        let index = match self
+            .plugins
+            .iter()
+            .position(|timing| timing.timing.name == plugin)
+        {
+            Some(index) => index,
+            None => {
+                self.plugins.push(PluginTiming {
+                    timing: Timing::new(plugin.to_string()),
+                    systems: Vec::new(),
+                });
+                self.plugins.len() - 1
+            }
+        };
+        &mut self.plugins[index]
+    }
+
+    /// Returns the counter of the system, shared by the instances of the system in the plugin
+    fn register_system(&mut self, plugin: &str, system: &str) -> Arc<AtomicU64> {
+        let plugin_timing = self.register_plugin(plugin);
+
+        let name = format!("{plugin}::{system}");
+        if let Some(system) = plugin_timing
+            .systems
+            .iter()
+            .find(|system| system.timing.name == name)
+        {
+            return system.elapsed.clone();
+        }
+
+        // The system did not run in the frames already collected for the plugin
+        let mut timing = Timing::new(name);
+        timing
+            .history
+            .resize(plugin_timing.timing.history.len(), Duration::ZERO);
+
+        let elapsed = Arc::new(AtomicU64::new(0));
+        plugin_timing.systems.push(SystemTiming {
+            timing,
+            elapsed: elapsed.clone(),
+        });
+        elapsed
+    }
+}
+
+impl PluginTiming {
+    /// Timing of all of the systems of the plugin
+    pub fn timing(&self) -> &Timing {
+        &self.timing
+    }
+
+    /// Timings of the systems of the plugin, named `<Plugin>::<system>`
+    pub fn systems(&self) -> impl Iterator<Item = &Timing> {
+        self.systems.iter().map(|system| &system.timing)
+    }
+
+    pub fn system(&self, name: &str) -> Option<&Timing> {
+        self.systems().find(|timing| timing.name == name)
+    }
+}
+
+impl Timing {
+    fn new(name: String) -> Self {
+        let mut hasher = DefaultHasher::new();
+        name.hash(&mut hasher);
+
+        Self {
+            diagnostic_id: DiagnosticId(Uuid::from_u64_pair(
+                0x6765_6e5f_706c_7567,
+                hasher.finish(),
+            )),
+            name,
+            history: VecDeque::with_capacity(HISTORY_LENGTH),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Identifier of the diagnostic of the timing in the [`DiagnosticsStore`]
+    pub fn diagnostic_id(&self) -> DiagnosticId {
+        self.diagnostic_id
+    }
+
+    /// Time spent during the last collected frame
+    pub fn last(&self) -> Duration {
+        self.history.back().copied().unwrap_or_default()
+    }
+
+    /// Average time spent per frame over the last 120 frames
+    pub fn average(&self) -> Duration {
+        match self.history.len() {
+            0 => Duration::ZERO,
+            len => self.history.iter().sum::<Duration>() / len as u32,
+        }
+    }
+
+    /// Name of the diagnostic, the end of the name is kept when it is longer than
+    /// [`MAX_DIAGNOSTIC_NAME_WIDTH`] as the system is more specific than the plugin
+    fn diagnostic_name(&self) -> String {
+        let len = self.name.chars().count();
+        if len <= MAX_DIAGNOSTIC_NAME_WIDTH {
+            return self.name.clone();
+        }
+        let end = self
+            .name
+            .chars()
+            .skip(len - (MAX_DIAGNOSTIC_NAME_WIDTH - 1));
+        std::iter::once('…').chain(end).collect()
+    }
+
+    fn push(&mut self, elapsed: Duration, diagnostics: &mut Option<ResMut<DiagnosticsStore>>) {
+        if self.history.len() == HISTORY_LENGTH {
+            self.history.pop_front();
+        }
+        self.history.push_back(elapsed);
+
+        let Some(diagnostics) = diagnostics else {
+            return;
+        };
+        if diagnostics.get(self.diagnostic_id).is_none() {
+            diagnostics.add(
+                Diagnostic::new(self.diagnostic_id, self.diagnostic_name(), HISTORY_LENGTH)
+                    .with_suffix("ms"),
+            );
+        }
+        if let Some(diagnostic) = diagnostics.get_mut(self.diagnostic_id) {
+            diagnostic.add_measurement(DiagnosticMeasurement {
+                time: Instant::now(),
+                value: elapsed.as_secs_f64() * 1000.0,
+            });
+        }
+    }
+}
+
+/// Starts collecting the timings of the systems of the plugin
+#[doc(hidden)]
+pub fn profile_plugin(app: &mut App, plugin: &'static str) {
+    if !app.world.contains_resource::<PluginTimings>() {
+        app.init_resource::<PluginTimings>();
+        app.add_systems(First, collect_plugin_timings);
+    }
+    app.world
+        .resource_mut::<PluginTimings>()
+        .register_plugin(&get_short_name(plugin));
+}
+
+fn collect_plugin_timings(
+    mut timings: ResMut<PluginTimings>,
+    mut diagnostics: Option<ResMut<DiagnosticsStore>>,
+) {
+    for plugin in &mut timings.plugins {
+        let mut plugin_elapsed = Duration::ZERO;
+        for system in &mut plugin.systems {
+            let elapsed = Duration::from_nanos(system.elapsed.swap(0, Ordering::Relaxed));
+            plugin_elapsed += elapsed;
+            system.timing.push(elapsed, &mut diagnostics);
+        }
+        plugin.timing.push(plugin_elapsed, &mut diagnostics);
+    }
+}
+
+/// Wraps the system of a plugin with a `profile` section, `enabled` is false
+/// when the `cfg` of the section disables it
+#[doc(hidden)]
+pub fn profile_system<In, Out, Marker>(
+    system: impl IntoSystem<In, Out, Marker>,
+    plugin: &'static str,
+    enabled: bool,
+) -> impl System<In = In, Out = Out> {
+    ProfiledSystem {
+        system: IntoSystem::into_system(system),
+        plugin,
+        enabled,
+        elapsed: None,
+        span: Span::none(),
+    }
+}
+
+/// Runs the system in a `plugin_system` span named `<Plugin>::<system>` and adds the
+/// time spent to its [`PluginTimings`], the system keeps the name and sets of the
+/// wrapped system so it can still be ordered against
+struct ProfiledSystem<S> {
+    system: S,
+    plugin: &'static str,
+    enabled: bool,
+    elapsed: Option<Arc<AtomicU64>>,
+    span: Span,
+}
+
+impl<S: System> ProfiledSystem<S> {
+    fn profile<T>(elapsed: &Option<Arc<AtomicU64>>, span: &Span, run: impl FnOnce() -> T) -> T {
+        let Some(elapsed) = elapsed else {
+            return run();
+        };
+
+        let _guard = span.enter();
+        let start = Instant::now();
+        let output = run();
+        elapsed.fetch_add(start.elapsed().as_nanos() as u64, Ordering::Relaxed);
+        output
+    }
+}
+
+impl<S: System> System for ProfiledSystem<S> {
+    type In = S::In;
+    type Out = S::Out;
+
+    fn name(&self) -> Cow<'static, str> {
+        self.system.name()
+    }
+
+    fn type_id(&self) -> TypeId {
+        self.system.type_id()
+    }
+
+    fn component_access(&self) -> &Access<ComponentId> {
+        self.system.component_access()
+    }
+
+    fn archetype_component_access(&self) -> &Access<ArchetypeComponentId> {
+        self.system.archetype_component_access()
+    }
+
+    fn is_send(&self) -> bool {
+        self.system.is_send()
+    }
+
+    fn is_exclusive(&self) -> bool {
+        self.system.is_exclusive()
+    }
+
+    unsafe fn run_unsafe(&mut self, input: S::In, world: UnsafeWorldCell) -> S::Out {
+        Self::profile(&self.elapsed, &self.span, || {
+            // SAFETY: the caller upholds the requirements of the wrapped system
+            unsafe { self.system.run_unsafe(input, world) }
+        })
+    }
+
+    fn run(&mut self, input: S::In, world: &mut World) -> S::Out {
+        // Exclusive systems only implement `run`
+        Self::profile(&self.elapsed, &self.span, || self.system.run(input, world))
+    }
+
+    fn apply_deferred(&mut self, world: &mut World) {
+        self.system.apply_deferred(world);
+    }
+
+    fn initialize(&mut self, world: &mut World) {
+        self.system.initialize(world);
+
+        if self.enabled && self.elapsed.is_none() {
+            let plugin = get_short_name(self.plugin);
+            let system = get_short_name(&self.system.name());
+            self.elapsed = Some(
+                world
+                    .get_resource_or_insert_with(PluginTimings::default)
+                    .register_system(&plugin, &system),
+            );
+            self.span = info_span!("plugin_system", name = format!("{plugin}::{system}"));
+        }
+    }
+
+    fn update_archetype_component_access(&mut self, world: UnsafeWorldCell) {
+        self.system.update_archetype_component_access(world);
+    }
+
+    fn check_change_tick(&mut self, change_tick: Tick) {
+        self.system.check_change_tick(change_tick);
+    }
+
+    fn default_system_sets(&self) -> Vec<InternedSystemSet> {
+        self.system.default_system_sets()
+    }
+
+    fn get_last_run(&self) -> Tick {
+        self.system.get_last_run()
+    }
+
+    fn set_last_run(&mut self, last_run: Tick) {
+        self.system.set_last_run(last_run);
+    }
+}
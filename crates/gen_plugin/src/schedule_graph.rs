@@ -0,0 +1,394 @@
+use std::{
+    collections::{HashMap, HashSet},
+    io::{self, Write},
+};
+
+use bevy_app::App;
+use bevy_ecs::schedule::{BoxedCondition, NodeId, Schedule, Schedules};
+use bevy_utils::{
+    get_short_name,
+    petgraph::{graphmap::DiGraphMap, Direction::Incoming, Direction::Outgoing},
+};
+use serde::Serialize;
+
+use crate::GeneratedPlugins;
+
+/// Format written by [`write_schedule_graphs`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ScheduleGraphFormat {
+    /// A Graphviz `digraph` per schedule, the systems of each generated plugin are
+    /// grouped in a cluster
+    Dot,
+    /// An array of the schedules with their nodes and edges, needs the `json` feature
+    #[cfg(feature = "json")]
+    Json,
+}
+
+/// Writes the systems and sets of every schedule of the app, sorted by schedule label
+///
+/// The systems are grouped by the generated plugin whose set contains them, with their
+/// run conditions (state conditions like `in_state` are listed apart), the edges are the
+/// orderings (`before`, `after` and `chain`) and the sets containing the nodes. Systems
+/// added more than once to a schedule are flagged as duplicated
+///
+/// The systems of a schedule are only accessible until it is initialized, this must be
+/// called before the first update (e.g. in [`Plugin::finish`](bevy_app::Plugin::finish)),
+/// an error is returned for the schedules already initialized
+pub fn write_schedule_graphs(
+    app: &App,
+    format: ScheduleGraphFormat,
+    mut writer: impl Write,
+) -> io::Result<()> {
+    let graphs = schedule_graphs(app)?;
+
+    match format {
+        ScheduleGraphFormat::Dot => {
+            for graph in &graphs {
+                graph.write_dot(&mut writer)?;
+            }
+            Ok(())
+        }
+        #[cfg(feature = "json")]
+        ScheduleGraphFormat::Json => {
+            serde_json::to_writer_pretty(&mut writer, &graphs)?;
+            writeln!(writer)
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ScheduleGraph {
+    schedule: String,
+    /// Run conditions of the sets of the generated plugins in the schedule
+    plugins: Vec<PluginNode>,
+    nodes: Vec<Node>,
+    edges: Vec<Edge>,
+}
+
+#[derive(Serialize)]
+struct PluginNode {
+    name: String,
+    conditions: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct Node {
+    #[serde(skip)]
+    node_id: NodeId,
+    id: String,
+    kind: NodeKind,
+    name: String,
+    /// Generated plugin whose set contains the node
+    plugin: Option<String>,
+    conditions: Vec<String>,
+    states: Vec<String>,
+    duplicated: bool,
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum NodeKind {
+    System,
+    Set,
+}
+
+#[derive(Serialize)]
+struct Edge {
+    from: String,
+    to: String,
+    kind: EdgeKind,
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum EdgeKind {
+    /// `from` runs before `to`
+    Before,
+    /// `from` is a set containing `to`
+    InSet,
+}
+
+fn schedule_graphs(app: &App) -> io::Result<Vec<ScheduleGraph>> {
+    let plugin_sets = app
+        .world
+        .get_resource::<GeneratedPlugins>()
+        .map(|plugins| {
+            plugins
+                .iter()
+                .map(|plugin| (format!("{}Set", plugin.name), plugin.name.to_string()))
+                .collect::<HashMap<_, _>>()
+        })
+        .unwrap_or_default();
+
+    let mut graphs = app
+        .world
+        .get_resource::<Schedules>()
+        .into_iter()
+        .flat_map(|schedules| schedules.iter())
+        .map(|(label, schedule)| ScheduleGraph::new(format!("{label:?}"), schedule, &plugin_sets))
+        .collect::<io::Result<Vec<_>>>()?;
+
+    graphs.sort_by(|a, b| a.schedule.cmp(&b.schedule));
+    Ok(graphs)
+}
+
+impl ScheduleGraph {
+    fn new(
+        schedule_name: String,
+        schedule: &Schedule,
+        plugin_sets: &HashMap<String, String>,
+    ) -> io::Result<Self> {
+        let graph = schedule.graph();
+        let hierarchy = graph.hierarchy().graph();
+
+        // Initializing the schedule moves its systems and conditions out of the graph,
+        // only their ids are left in the dependency graph
+        if graph.systems().next().is_none()
+            && graph
+                .dependency()
+                .graph()
+                .nodes()
+                .any(|node| node.is_system())
+        {
+            return Err(io::Error::other(format!(
+                "the schedule {schedule_name} is already initialized, \
+                its systems are only accessible before the first update"
+            )));
+        }
+        let mut export = Self {
+            schedule: schedule_name,
+            plugins: Vec::new(),
+            nodes: Vec::new(),
+            edges: Vec::new(),
+        };
+
+        // Plugin sets are shown as the `plugin` of their nodes, system type sets
+        // (the targets of orderings like `after = my_system`) as their systems
+        let mut plugins = HashMap::new();
+        let mut system_type_sets = HashSet::new();
+        for (id, set, conditions) in graph.system_sets() {
+            let name = format!("{set:?}");
+            if let Some(plugin) = plugin_sets.get(&name) {
+                plugins.insert(id, plugin.clone());
+                // The sets of the instances of a generic plugin have the same name
+                if export.plugins.iter().all(|node| node.name != *plugin) {
+                    export.plugins.push(PluginNode {
+                        name: plugin.clone(),
+                        conditions: conditions.iter().map(condition_name).collect(),
+                    });
+                }
+            } else if set.system_type().is_some() {
+                system_type_sets.insert(id);
+            } else {
+                export
+                    .nodes
+                    .push(Node::new(id, NodeKind::Set, name, conditions));
+            }
+        }
+
+        let mut counts = HashMap::<String, usize>::new();
+        for (id, system, conditions) in graph.systems() {
+            let name = get_short_name(&system.name());
+            *counts.entry(name.clone()).or_default() += 1;
+            export
+                .nodes
+                .push(Node::new(id, NodeKind::System, name, conditions));
+        }
+
+        for node in &mut export.nodes {
+            node.duplicated = node.kind == NodeKind::System && counts[&node.name] > 1;
+            node.plugin = find_plugin(hierarchy, &plugins, node.node_id);
+        }
+
+        // Sets outside of the plugin sets (e.g. the anonymous sets of the conditions of
+        // `systems` sections) are grouped with their nodes if they are all in a plugin
+        let node_plugins = export
+            .nodes
+            .iter()
+            .map(|node| (node.node_id, node.plugin.clone()))
+            .collect::<HashMap<_, _>>();
+        for node in &mut export.nodes {
+            if node.kind == NodeKind::Set && node.plugin.is_none() {
+                let mut children = hierarchy
+                    .neighbors_directed(node.node_id, Outgoing)
+                    .map(|child| node_plugins.get(&child).cloned().flatten());
+                if let Some(Some(plugin)) = children.next() {
+                    if children.all(|child| child.as_ref() == Some(&plugin)) {
+                        node.plugin = Some(plugin);
+                    }
+                }
+            }
+        }
+
+        let exported = |id: &NodeId| !plugins.contains_key(id) && !system_type_sets.contains(id);
+        // Orderings against a system type set apply to the systems of the type
+        let resolve = |id: NodeId| match system_type_sets.contains(&id) {
+            true => hierarchy.neighbors_directed(id, Outgoing).collect(),
+            false => vec![id],
+        };
+
+        for (before, after, _) in graph.dependency().graph().all_edges() {
+            for before in resolve(before) {
+                for after in resolve(after) {
+                    if exported(&before) && exported(&after) {
+                        export
+                            .edges
+                            .push(Edge::new(before, after, EdgeKind::Before));
+                    }
+                }
+            }
+        }
+        for (set, node, _) in hierarchy.all_edges() {
+            if exported(&set) && exported(&node) {
+                export.edges.push(Edge::new(set, node, EdgeKind::InSet));
+            }
+        }
+
+        Ok(export)
+    }
+
+    fn write_dot(&self, writer: &mut impl Write) -> io::Result<()> {
+        writeln!(writer, "digraph {} {{", quote(&self.schedule))?;
+        writeln!(writer, "    rankdir=LR;")?;
+        writeln!(writer, "    node [shape=box];")?;
+
+        let dot_node = |node: &Node| {
+            let mut label = node.name.clone();
+            for condition in &node.conditions {
+                label += &format!("\\nif {condition}");
+            }
+            for state in &node.states {
+                label += &format!("\\nstate {state}");
+            }
+            if node.duplicated {
+                label += "\\n(duplicated)";
+            }
+
+            let style = match (node.kind, node.duplicated) {
+                (NodeKind::Set, _) => " style=dashed",
+                (NodeKind::System, true) => " color=red",
+                (NodeKind::System, false) => "",
+            };
+            format!("{} [label={}{style}];", quote(&node.id), quote(&label))
+        };
+
+        for plugin in &self.plugins {
+            let mut label = plugin.name.clone();
+            for condition in &plugin.conditions {
+                label += &format!("\\nif {condition}");
+            }
+
+            writeln!(
+                writer,
+                "    subgraph {} {{",
+                quote(&format!("cluster_{}", plugin.name))
+            )?;
+            writeln!(writer, "        label={};", quote(&label))?;
+            for node in &self.nodes {
+                if node.plugin.as_ref() == Some(&plugin.name) {
+                    writeln!(writer, "        {}", dot_node(node))?;
+                }
+            }
+            writeln!(writer, "    }}")?;
+        }
+        for node in &self.nodes {
+            if node.plugin.is_none() {
+                writeln!(writer, "    {}", dot_node(node))?;
+            }
+        }
+
+        for edge in &self.edges {
+            let style = match edge.kind {
+                EdgeKind::Before => "",
+                EdgeKind::InSet => " [style=dashed arrowhead=none]",
+            };
+            writeln!(
+                writer,
+                "    {} -> {}{style};",
+                quote(&edge.from),
+                quote(&edge.to)
+            )?;
+        }
+
+        writeln!(writer, "}}")
+    }
+}
+
+impl Node {
+    fn new(node_id: NodeId, kind: NodeKind, name: String, conditions: &[BoxedCondition]) -> Self {
+        let (states, conditions) = conditions
+            .iter()
+            .map(condition_name)
+            .partition(|name| STATE_CONDITIONS.iter().any(|state| name.starts_with(state)));
+
+        Self {
+            node_id,
+            id: node_id_string(node_id),
+            kind,
+            name,
+            plugin: None,
+            conditions,
+            states,
+            duplicated: false,
+        }
+    }
+}
+
+impl Edge {
+    fn new(from: NodeId, to: NodeId, kind: EdgeKind) -> Self {
+        Self {
+            from: node_id_string(from),
+            to: node_id_string(to),
+            kind,
+        }
+    }
+}
+
+/// Conditions of bevy gating systems on states
+const STATE_CONDITIONS: &[&str] = &[
+    "in_state",
+    "state_exists",
+    "state_changed",
+    "state_exists_and_equals",
+];
+
+fn condition_name(condition: &BoxedCondition) -> String {
+    let name = get_short_name(&condition.name());
+    match name.strip_suffix("::{{closure}}") {
+        Some(name) => name.to_string(),
+        None => name,
+    }
+}
+
+/// Plugin whose set contains the node, directly or through other sets
+fn find_plugin(
+    hierarchy: &DiGraphMap<NodeId, ()>,
+    plugins: &HashMap<NodeId, String>,
+    node: NodeId,
+) -> Option<String> {
+    let mut seen = HashSet::new();
+    let mut queue = vec![node];
+    while let Some(node) = queue.pop() {
+        if let Some(plugin) = plugins.get(&node) {
+            return Some(plugin.clone());
+        }
+        queue.extend(
+            hierarchy
+                .neighbors_directed(node, Incoming)
+                .filter(|set| seen.insert(*set)),
+        );
+    }
+    None
+}
+
+fn node_id_string(id: NodeId) -> String {
+    match id {
+        NodeId::System(index) => format!("system_{index}"),
+        NodeId::Set(index) => format!("set_{index}"),
+    }
+}
+
+/// Quotes a DOT identifier
+fn quote(id: &str) -> String {
+    format!("\"{}\"", id.replace('"', "\\\""))
+}
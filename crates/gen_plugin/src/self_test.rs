@@ -0,0 +1,120 @@
+use bevy_app::{App, PluginsState, ScheduleRunnerPlugin};
+use bevy_core::{FrameCountPlugin, TaskPoolPlugin, TypeRegistrationPlugin};
+use bevy_ecs::{
+    schedule::{NodeId, ScheduleGraph, ScheduleLabel, Schedules, SystemSet},
+    system::Resource,
+    world::Mut,
+};
+use bevy_time::TimePlugin;
+use bevy_utils::{petgraph::Direction, HashSet};
+
+use crate::PluginSystemSet;
+
+/// Inserted by the `self_test` sections, makes the invariant violations panic
+#[doc(hidden)]
+#[derive(Resource)]
+pub struct SelfTest;
+
+/// Headless [`App`] with the plugins of `MinimalPlugins`
+#[doc(hidden)]
+pub fn self_test_app() -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        TaskPoolPlugin::default(),
+        TypeRegistrationPlugin,
+        FrameCountPlugin,
+        TimePlugin,
+        ScheduleRunnerPlugin::default(),
+    ));
+    app.insert_resource(SelfTest);
+    app
+}
+
+/// Label given to the schedules built by [`run_self_test`], the labels of
+/// [`Schedules`] cannot be interned again from `&dyn ScheduleLabel`
+#[derive(ScheduleLabel, Clone, Debug, PartialEq, Eq, Hash)]
+struct SelfTestSchedule;
+
+/// Panics if a system of the plugin is ambiguous, then runs `frames` updates
+#[doc(hidden)]
+pub fn run_self_test<P: PluginSystemSet>(app: &mut App, frames: usize) {
+    while app.plugins_state() == PluginsState::Adding {
+        std::thread::yield_now();
+    }
+    app.finish();
+    app.cleanup();
+
+    let ambiguities = app
+        .world
+        .resource_scope(|world, mut schedules: Mut<Schedules>| {
+            let ignored = schedules.ignored_scheduling_ambiguities.clone();
+            schedules
+                .iter_mut()
+                .flat_map(|(label, schedule)| {
+                    // The systems are only accessible from the graph before the
+                    // first run, the schedule is built once to find the conflicts
+                    let graph = schedule.graph_mut();
+                    graph.initialize(world);
+                    if let Err(error) = graph.build_schedule(
+                        world.components(),
+                        SelfTestSchedule.intern(),
+                        &ignored,
+                    ) {
+                        panic!("Error when building schedule {label:?}: {error}");
+                    }
+                    plugin_ambiguities::<P>(graph)
+                        .into_iter()
+                        .map(move |(a, b)| format!("\n\t{label:?}: `{a}` and `{b}`"))
+                })
+                .collect::<Vec<String>>()
+        });
+
+    assert!(
+        ambiguities.is_empty(),
+        "`{}` has ambiguous systems:{}",
+        std::any::type_name::<P>(),
+        ambiguities.concat(),
+    );
+
+    for _ in 0..frames {
+        app.update();
+    }
+}
+
+/// Names of the conflicting systems where at least one of them is in the set of the plugin
+fn plugin_ambiguities<P: PluginSystemSet>(graph: &ScheduleGraph) -> Vec<(String, String)> {
+    let plugin_set = P::SystemSet::default();
+    let Some((plugin_set_id, _, _)) = graph
+        .system_sets()
+        .find(|(_, set, _)| set.as_dyn_eq().dyn_eq(plugin_set.as_dyn_eq()))
+    else {
+        return Vec::new();
+    };
+
+    // Systems and sets in the set of the plugin, directly or through sub-sets
+    let mut in_plugin_set = HashSet::<NodeId>::new();
+    let mut stack = vec![plugin_set_id];
+    while let Some(node) = stack.pop() {
+        for child in graph
+            .hierarchy()
+            .graph()
+            .neighbors_directed(node, Direction::Outgoing)
+        {
+            if in_plugin_set.insert(child) {
+                stack.push(child);
+            }
+        }
+    }
+
+    graph
+        .conflicting_systems()
+        .iter()
+        .filter(|(a, b, _)| in_plugin_set.contains(a) || in_plugin_set.contains(b))
+        .map(|(a, b, _)| {
+            (
+                graph.system_at(*a).name().to_string(),
+                graph.system_at(*b).name().to_string(),
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,113 @@
+use std::marker::PhantomData;
+
+use bevy_app::App;
+use bevy_asset::{
+    io::Reader, Asset, AssetApp, AssetEvent, AssetLoader, AssetPath, AssetServer, Assets,
+    AsyncReadExt, Handle, LoadContext,
+};
+use bevy_ecs::{
+    event::EventReader,
+    system::{Res, ResMut, Resource},
+};
+use bevy_log::error;
+use bevy_reflect::TypePath;
+use bevy_utils::BoxedFuture;
+use serde::de::DeserializeOwned;
+
+/// Content of a settings file, parsed by the systems of the `settings` sections
+/// so a parsing error can fall back to the default settings
+#[derive(Asset, TypePath)]
+pub struct SettingsFile(Vec<u8>);
+
+/// Extension of the settings files, loaded by [`SettingsFileLoader`]
+const SETTINGS_EXTENSION: &str = "settings.ron";
+
+#[derive(Default)]
+struct SettingsFileLoader;
+
+impl AssetLoader for SettingsFileLoader {
+    type Asset = SettingsFile;
+    type Settings = ();
+    type Error = std::io::Error;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<SettingsFile, std::io::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            Ok(SettingsFile(bytes))
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &[SETTINGS_EXTENSION]
+    }
+}
+
+/// Handle of the file of the settings `S`, kept to receive its reloads
+#[derive(Resource)]
+pub struct SettingsHandle<S> {
+    path: AssetPath<'static>,
+    handle: Handle<SettingsFile>,
+    marker: PhantomData<fn() -> S>,
+}
+
+/// Starts loading the file of the settings `S`, called in [`Plugin::finish`](bevy_app::Plugin::finish)
+/// so the [`AssetPlugin`](bevy_asset::AssetPlugin) can be added after the plugin
+#[doc(hidden)]
+pub fn load_settings<S: Resource>(app: &mut App, path: impl Into<AssetPath<'static>>) {
+    let path = path.into();
+    let type_name = std::any::type_name::<S>();
+    if path.get_full_extension().as_deref() != Some(SETTINGS_EXTENSION) {
+        panic!("the settings file `{path}` of `{type_name}` must have the `.{SETTINGS_EXTENSION}` extension");
+    }
+    if !app.world.contains_resource::<AssetServer>() {
+        panic!("the settings `{type_name}` are loaded from `{path}` by the `AssetServer`, add the `AssetPlugin` to the app");
+    }
+
+    if !app.world.contains_resource::<Assets<SettingsFile>>() {
+        app.init_asset::<SettingsFile>()
+            .init_asset_loader::<SettingsFileLoader>();
+    }
+
+    let handle = app.world.resource::<AssetServer>().load(path.clone());
+    app.insert_resource(SettingsHandle::<S> {
+        path,
+        handle,
+        marker: PhantomData,
+    });
+}
+
+/// Parses the settings file when it is loaded or modified, falls back to the
+/// default settings if it cannot be parsed
+#[doc(hidden)]
+pub fn apply_settings<S: Resource + Default + DeserializeOwned>(
+    mut events: EventReader<AssetEvent<SettingsFile>>,
+    files: Res<Assets<SettingsFile>>,
+    handle: Res<SettingsHandle<S>>,
+    mut settings: ResMut<S>,
+) {
+    let id = handle.handle.id();
+    if !events
+        .read()
+        .any(|event| event.is_added(id) || event.is_modified(id))
+    {
+        return;
+    }
+    let Some(SettingsFile(bytes)) = files.get(id) else {
+        return;
+    };
+
+    *settings = ron::de::from_bytes(bytes).unwrap_or_else(|error| {
+        error!(
+            "Invalid settings `{}` for `{}`, using the default settings: {error}",
+            handle.path,
+            std::any::type_name::<S>(),
+        );
+        S::default()
+    });
+}
@@ -0,0 +1,23 @@
+use bevy_asset::{Asset, Assets, Handle};
+use bevy_ecs::{
+    system::{AdapterSystem, Commands, IntoSystem, System},
+    world::World,
+};
+
+/// System of a `spawn` section of [`gen_plugin!`](crate::gen_plugin) named `<Plugin>::spawn`,
+/// the entities are spawned by a command so the system does not conflict with the others
+#[doc(hidden)]
+pub fn spawn_system(plugin: &'static str, spawn: fn(&mut World)) -> impl System<In = (), Out = ()> {
+    AdapterSystem::new(
+        |()| {},
+        IntoSystem::into_system(move |mut commands: Commands| commands.add(spawn)),
+        format!("{plugin}::spawn").into(),
+    )
+}
+
+/// Adds the argument of an `asset(...)` of a `spawn` section of [`gen_plugin!`](crate::gen_plugin)
+/// to the [`Assets`] of the handle expected in its place (e.g. `Handle<Mesh>` for a mesh)
+#[doc(hidden)]
+pub fn spawn_asset<A: Asset>(world: &mut World, asset: impl Into<A>) -> Handle<A> {
+    world.resource_mut::<Assets<A>>().add(asset.into())
+}
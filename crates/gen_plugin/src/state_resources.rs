@@ -0,0 +1,30 @@
+use std::any::TypeId;
+
+use bevy_ecs::{
+    schedule::SystemSet,
+    system::Resource,
+    world::{FromWorld, World},
+};
+
+/// Sets of the systems of the `state_resources` sections of a plugin, identified by
+/// the [`TypeId`] of the plugin, its other systems run after the resources are
+/// initialized and before they are removed
+#[doc(hidden)]
+#[derive(SystemSet, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum StateResourcesSet {
+    Init(TypeId),
+    Remove(TypeId),
+}
+
+/// Exclusive system initializing the resource on [`OnEnter`](bevy_ecs::schedule::OnEnter),
+/// so it is available to the systems running after it in the same schedule
+#[doc(hidden)]
+pub fn init_state_resource<R: Resource + FromWorld>(world: &mut World) {
+    world.init_resource::<R>();
+}
+
+/// Exclusive system removing the resource on [`OnExit`](bevy_ecs::schedule::OnExit)
+#[doc(hidden)]
+pub fn remove_state_resource<R: Resource>(world: &mut World) {
+    world.remove_resource::<R>();
+}
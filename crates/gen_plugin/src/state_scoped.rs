@@ -0,0 +1,26 @@
+use bevy_ecs::{
+    component::Component,
+    entity::Entity,
+    schedule::States,
+    system::{Commands, Query},
+};
+use bevy_hierarchy::DespawnRecursiveExt;
+
+/// Despawns the entity and its descendants when exiting the state, if a `state_scoped`
+/// section of [`gen_plugin!`](crate::gen_plugin) declares the state
+#[derive(Component, Clone, Debug)]
+pub struct StateScoped<S: States>(pub S);
+
+/// System despawning the entities scoped to the state, ran on [`OnExit`](bevy_ecs::schedule::OnExit)
+#[doc(hidden)]
+pub fn despawn_state_scoped<S: States>(
+    state: S,
+) -> impl FnMut(Commands, Query<(Entity, &StateScoped<S>)>) + Send + Sync + 'static {
+    move |mut commands, entities| {
+        for (entity, scoped) in &entities {
+            if scoped.0 == state {
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+    }
+}
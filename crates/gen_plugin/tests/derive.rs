@@ -0,0 +1,21 @@
+use bevy_app::{App, Update};
+use bevy_denshi_ika_gen_plugin::{self as renamed, GenPlugin, GeneratedPlugins};
+
+fn tick() {}
+
+#[derive(GenPlugin, Default)]
+#[gen_plugin(crate = renamed)]
+#[gen_plugin(systems(Update)(tick);)]
+struct TickPlugin;
+
+#[test]
+fn crate_path() {
+    let mut app = App::new();
+    app.add_plugins(TickPlugin);
+
+    assert!(app
+        .world
+        .resource::<GeneratedPlugins>()
+        .iter()
+        .any(|plugin| plugin.name == "TickPlugin"));
+}
@@ -0,0 +1,22 @@
+use bevy_app::App;
+use bevy_denshi_ika_gen_plugin::gen_plugin;
+use bevy_time::{Fixed, Time};
+use bevy_utils::Duration;
+
+fn physics_system() {}
+
+gen_plugin! {
+    PhysicsPlugin;
+    fixed(hz = 50)(physics_system);
+}
+
+#[test]
+fn integer_rate() {
+    let mut app = App::new();
+    app.add_plugins(PhysicsPlugin);
+
+    assert_eq!(
+        app.world.resource::<Time<Fixed>>().timestep(),
+        Duration::from_millis(20),
+    );
+}
@@ -0,0 +1,54 @@
+use bevy_app::App;
+use bevy_denshi_ika_gen_plugin::{gen_plugin, InvariantSeverity, InvariantViolated};
+use bevy_ecs::{component::Component, event::Events, query::With};
+use bevy_hierarchy::BuildWorldChildren;
+
+#[derive(Component)]
+struct Root;
+
+#[derive(Component)]
+struct Leaf;
+
+#[derive(Component)]
+struct Branch;
+
+gen_plugin! {
+    TreePlugin;
+    events(InvariantViolated);
+    invariants(With<Root>, severity = InvariantSeverity::Event)(children(Leaf));
+    invariants(With<Leaf>, severity = InvariantSeverity::Event)(parent(Root));
+    test_has(With<Branch>, (Leaf));
+}
+
+fn violations(app: &mut App) -> Vec<&'static str> {
+    app.world
+        .resource_mut::<Events<InvariantViolated>>()
+        .drain()
+        .map(|violation| violation.invariant)
+        .collect()
+}
+
+#[test]
+fn parent_and_children_need_the_relation() {
+    let mut app = App::new();
+    app.add_plugins(TreePlugin);
+
+    app.world.spawn(Root);
+    app.world.spawn(Leaf);
+    app.update();
+    let mut reported = violations(&mut app);
+    reported.sort();
+    assert_eq!(
+        reported,
+        [
+            "have a parent with `Root`",
+            "have children, all with `Leaf`"
+        ],
+    );
+
+    app.world.spawn(Root).with_children(|root| {
+        root.spawn(Leaf);
+    });
+    app.update();
+    assert!(violations(&mut app).is_empty());
+}
@@ -0,0 +1,86 @@
+use bevy_app::{App, Update};
+use bevy_denshi_ika_gen_plugin::{gen_plugin, GeneratedPlugins};
+use bevy_ecs::{
+    event::Event,
+    schedule::{IntoSystemConfigs, Schedules},
+    system::Resource,
+};
+
+#[derive(Resource, Default)]
+struct Counter;
+
+#[derive(Event)]
+struct Hit;
+
+mod first {
+    pub fn sys() {}
+
+    pub fn other_sys() {}
+}
+
+mod second {
+    pub fn sys() {}
+}
+
+gen_plugin! {
+    FirstPlugin;
+    events(Hit);
+    resources(Counter);
+    systems(Update)((first::sys, first::other_sys).chain());
+}
+
+gen_plugin! {
+    SecondPlugin;
+    init_resources(Counter);
+    systems(Update)(second::sys.after(first::sys));
+}
+
+fn app() -> App {
+    let mut app = App::new();
+    app.add_plugins((FirstPlugin, SecondPlugin));
+    app
+}
+
+#[test]
+fn systems_are_recorded_individually() {
+    let app = app();
+    let plugins = app.world.resource::<GeneratedPlugins>();
+    let owner = |system: &str| plugins.owner_of_system(system).map(|plugin| plugin.name);
+
+    assert_eq!(owner("manifest::first::sys"), Some("FirstPlugin"));
+    assert_eq!(owner("manifest::first::other_sys"), Some("FirstPlugin"));
+    assert_eq!(owner("manifest::second::sys"), Some("SecondPlugin"));
+    assert_eq!(owner("sys"), None);
+    assert_eq!(owner("chain"), None);
+    assert_eq!(owner("after"), None);
+    assert_eq!(owner("default"), None);
+}
+
+#[test]
+fn systems_are_found_from_the_schedule() {
+    let app = app();
+    let plugins = app.world.resource::<GeneratedPlugins>();
+    let schedules = app.world.resource::<Schedules>();
+
+    let owners = schedules
+        .get(Update)
+        .unwrap()
+        .graph()
+        .systems()
+        .map(|(_, system, _)| plugins.owner_of_system(&system.name()).unwrap().name)
+        .collect::<Vec<&str>>();
+
+    assert_eq!(owners, ["FirstPlugin", "FirstPlugin", "SecondPlugin"]);
+}
+
+#[test]
+fn types_are_recorded_by_type_name() {
+    let app = app();
+    let plugins = app.world.resource::<GeneratedPlugins>();
+    let first = plugins.iter().next().unwrap();
+
+    assert!(first.owns_type(std::any::type_name::<Counter>()));
+    assert!(first.owns_type(std::any::type_name::<Hit>()));
+    assert!(!first.owns_type("default"));
+    assert!(!first.owns_type("Counter"));
+}
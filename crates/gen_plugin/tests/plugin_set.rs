@@ -0,0 +1,36 @@
+use bevy_app::{App, Update};
+use bevy_denshi_ika_gen_plugin::{gen_plugin, PluginSystemSet};
+use bevy_ecs::schedule::{Schedules, SystemSet};
+
+fn first_system() {}
+
+fn second_system() {}
+
+gen_plugin! {
+    TogglePlugin;
+    toggle(enabled = true);
+    before(OtherPlugin);
+    systems(Update)(first_system);
+    systems(bevy_app::Update)(second_system);
+}
+
+gen_plugin! {
+    OtherPlugin;
+    systems(Update)(second_system);
+}
+
+#[test]
+fn plugin_set_configured_once_per_schedule() {
+    let mut app = App::new();
+    app.add_plugins((TogglePlugin, OtherPlugin));
+
+    let schedules = app.world.resource::<Schedules>();
+    let graph = schedules.get(Update).unwrap().graph();
+    let plugin_set = <TogglePlugin as PluginSystemSet>::SystemSet::default();
+    let (_, _, conditions) = graph
+        .system_sets()
+        .find(|(_, set, _)| set.as_dyn_eq().dyn_eq(plugin_set.as_dyn_eq()))
+        .unwrap();
+
+    assert_eq!(conditions.len(), 1);
+}
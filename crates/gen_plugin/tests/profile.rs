@@ -0,0 +1,37 @@
+use bevy_app::{App, Update};
+use bevy_denshi_ika_gen_plugin::{gen_plugin, PluginTimings};
+use bevy_diagnostic::{DiagnosticsStore, MAX_DIAGNOSTIC_NAME_WIDTH};
+
+fn update_camera_spring_arm_shape_caster_transform() {}
+
+gen_plugin! {
+    CameraSpringArmPlugin;
+    profile;
+    systems(Update)(update_camera_spring_arm_shape_caster_transform);
+}
+
+#[test]
+fn diagnostic_names_fit() {
+    let mut app = App::new();
+    app.init_resource::<DiagnosticsStore>()
+        .add_plugins(CameraSpringArmPlugin);
+    app.update();
+    app.update();
+
+    let system = app
+        .world
+        .resource::<PluginTimings>()
+        .get("CameraSpringArmPlugin")
+        .unwrap()
+        .system("CameraSpringArmPlugin::update_camera_spring_arm_shape_caster_transform")
+        .unwrap()
+        .diagnostic_id();
+    let diagnostics = app.world.resource::<DiagnosticsStore>();
+    assert_eq!(
+        diagnostics.get(system).unwrap().name,
+        "…ring_arm_shape_caster_transform",
+    );
+    assert!(diagnostics
+        .iter()
+        .all(|diagnostic| diagnostic.name.chars().count() <= MAX_DIAGNOSTIC_NAME_WIDTH));
+}
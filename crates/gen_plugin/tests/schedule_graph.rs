@@ -0,0 +1,89 @@
+use bevy_app::{App, Update};
+use bevy_denshi_ika_gen_plugin::{gen_plugin, write_schedule_graphs, ScheduleGraphFormat};
+use bevy_ecs::schedule::{common_conditions::in_state, IntoSystemConfigs, States};
+
+#[derive(States, Clone, Debug, Default, PartialEq, Eq, Hash)]
+enum Mode {
+    #[default]
+    Edit,
+}
+
+fn editing() -> bool {
+    true
+}
+
+fn move_cursor() {}
+
+fn draw_cursor() {}
+
+gen_plugin! {
+    CursorPlugin;
+    states(Mode);
+    systems(Update)((
+        move_cursor.run_if(in_state(Mode::Edit)).run_if(editing),
+        draw_cursor,
+        draw_cursor,
+    ));
+}
+
+fn graphs(app: &App, format: ScheduleGraphFormat) -> String {
+    let mut output = Vec::new();
+    write_schedule_graphs(app, format, &mut output).unwrap();
+    String::from_utf8(output).unwrap()
+}
+
+#[test]
+fn dot() {
+    let mut app = App::new();
+    app.add_plugins(CursorPlugin);
+    let dot = graphs(&app, ScheduleGraphFormat::Dot);
+
+    let update = &dot[dot.find("digraph \"Update\"").unwrap()..];
+    let cluster = &update[update.find("subgraph \"cluster_CursorPlugin\"").unwrap()..];
+    let cluster = &cluster[..cluster.find("    }").unwrap()];
+    assert!(cluster.contains("move_cursor\\nif editing\\nstate in_state<Mode>\""));
+    assert_eq!(
+        cluster
+            .matches("draw_cursor\\n(duplicated)\" color=red")
+            .count(),
+        2
+    );
+}
+
+#[test]
+#[cfg(feature = "json")]
+fn json() {
+    let mut app = App::new();
+    app.add_plugins(CursorPlugin);
+    let json = graphs(&app, ScheduleGraphFormat::Json);
+
+    let graphs = serde_json::from_str::<serde_json::Value>(&json).unwrap();
+    let update = graphs
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|graph| graph["schedule"] == "Update")
+        .unwrap();
+    let nodes = update["nodes"].as_array().unwrap();
+    let move_cursor = nodes
+        .iter()
+        .find(|node| node["name"] == "move_cursor")
+        .unwrap();
+    assert_eq!(move_cursor["plugin"], "CursorPlugin");
+    assert_eq!(move_cursor["conditions"], serde_json::json!(["editing"]));
+    assert_eq!(move_cursor["states"], serde_json::json!(["in_state<Mode>"]));
+    assert!(nodes
+        .iter()
+        .filter(|node| node["name"] == "draw_cursor")
+        .all(|node| node["duplicated"] == true));
+}
+
+#[test]
+fn after_update() {
+    let mut app = App::new();
+    app.add_plugins(CursorPlugin);
+    app.update();
+
+    let error = write_schedule_graphs(&app, ScheduleGraphFormat::Dot, std::io::sink()).unwrap_err();
+    assert!(error.to_string().contains("already initialized"));
+}
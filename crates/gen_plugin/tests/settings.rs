@@ -0,0 +1,81 @@
+use bevy_app::App;
+use bevy_asset::{
+    io::Reader, Asset, AssetApp, AssetLoader, AssetPlugin, AsyncReadExt, LoadContext,
+};
+use bevy_core::TaskPoolPlugin;
+use bevy_denshi_ika_gen_plugin::gen_plugin;
+use bevy_ecs::system::Resource;
+use bevy_reflect::TypePath;
+use bevy_utils::{BoxedFuture, Duration, Instant};
+use serde::Deserialize;
+
+#[derive(Resource, Deserialize, Default)]
+struct AudioSettings {
+    volume: u32,
+}
+
+gen_plugin! {
+    AudioPlugin;
+    settings(AudioSettings = "audio.settings.ron");
+}
+
+#[derive(Asset, TypePath)]
+struct RonFile;
+
+#[derive(Default)]
+struct RonFileLoader;
+
+impl AssetLoader for RonFileLoader {
+    type Asset = RonFile;
+    type Settings = ();
+    type Error = std::io::Error;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<RonFile, std::io::Error>> {
+        Box::pin(async move {
+            reader.read_to_end(&mut Vec::new()).await?;
+            Ok(RonFile)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ron"]
+    }
+}
+
+#[test]
+fn plugin_before_asset_plugin() {
+    let mut app = App::new();
+    app.add_plugins((
+        AudioPlugin,
+        TaskPoolPlugin::default(),
+        AssetPlugin {
+            file_path: "tests/assets".to_owned(),
+            ..Default::default()
+        },
+    ))
+    .init_asset::<RonFile>()
+    .init_asset_loader::<RonFileLoader>();
+    app.finish();
+    app.cleanup();
+
+    let start = Instant::now();
+    while app.world.resource::<AudioSettings>().volume != 7 {
+        assert!(
+            start.elapsed() < Duration::from_secs(10),
+            "the settings file was not loaded",
+        );
+        app.update();
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[test]
+#[should_panic(expected = "add the `AssetPlugin` to the app")]
+fn missing_asset_plugin() {
+    App::new().add_plugins(AudioPlugin).run();
+}
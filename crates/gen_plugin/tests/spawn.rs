@@ -0,0 +1,70 @@
+use bevy_app::{App, Startup};
+use bevy_asset::{Asset, AssetApp, AssetPlugin, Assets, Handle};
+use bevy_core::Name;
+use bevy_denshi_ika_gen_plugin::gen_plugin;
+use bevy_ecs::{
+    component::Component,
+    system::{ResMut, Resource},
+};
+use bevy_hierarchy::Children;
+use bevy_reflect::TypePath;
+
+#[derive(Asset, TypePath)]
+struct Palette(u32);
+
+impl From<u32> for Palette {
+    fn from(color: u32) -> Self {
+        Self(color)
+    }
+}
+
+#[derive(Component)]
+struct Painted(Handle<Palette>);
+
+#[derive(Resource, Default)]
+struct Started(bool);
+
+fn start(mut started: ResMut<Started>) {
+    started.0 = true;
+}
+
+gen_plugin! {
+    LevelPlugin;
+    resources(Started::default());
+    systems(Startup)(start);
+    spawn(Startup)(
+        "Level" () children[
+            "Wall" (Painted(asset(0xff0000))),
+        ],
+    );
+    self_test(frames = 1, setup = |app: &mut App| {
+        app.add_plugins(AssetPlugin::default()).init_asset::<Palette>();
+    })();
+}
+
+#[test]
+fn spawned_after_startup() {
+    let mut app = App::new();
+    app.add_plugins((
+        bevy_core::TaskPoolPlugin::default(),
+        AssetPlugin::default(),
+        LevelPlugin,
+    ))
+    .init_asset::<Palette>();
+    app.update();
+
+    let mut levels = app.world.query::<(&Name, &Children)>();
+    let (name, children) = levels.single(&app.world);
+    assert_eq!(name.as_str(), "Level");
+
+    let painted = app.world.get::<Painted>(children[0]).unwrap();
+    assert_eq!(
+        app.world
+            .resource::<Assets<Palette>>()
+            .get(&painted.0)
+            .unwrap()
+            .0,
+        0xff0000,
+    );
+    assert!(app.world.resource::<Started>().0);
+}
@@ -0,0 +1,44 @@
+use bevy_app::App;
+use bevy_denshi_ika_gen_plugin::gen_plugin;
+use bevy_ecs::{
+    schedule::{NextState, States},
+    system::{Res, ResMut, Resource},
+};
+
+#[derive(States, Clone, Debug, Default, PartialEq, Eq, Hash)]
+enum Screen {
+    #[default]
+    Menu,
+    Level,
+}
+
+#[derive(Resource, Default)]
+struct Level;
+
+fn use_level(_level: Res<Level>) {}
+
+fn leave_level(mut next_state: ResMut<NextState<Screen>>) {
+    next_state.set(Screen::Menu);
+}
+
+gen_plugin! {
+    LevelPlugin;
+    states(Screen);
+    state_resources(Screen::Level)(Level);
+    on_enter(Screen::Level)((use_level, leave_level));
+    on_exit(Screen::Level)(use_level);
+    self_test(frames = 2)();
+}
+
+#[test]
+fn resources_available_on_enter_and_exit() {
+    let mut app = App::new();
+    app.add_plugins(LevelPlugin);
+    app.world.insert_resource(NextState(Some(Screen::Level)));
+
+    app.update();
+    assert!(app.world.contains_resource::<Level>());
+
+    app.update();
+    assert!(!app.world.contains_resource::<Level>());
+}
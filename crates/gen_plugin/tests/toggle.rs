@@ -0,0 +1,37 @@
+use bevy_app::App;
+use bevy_denshi_ika_gen_plugin::{gen_plugin, GenPlugin, PluginEnabled};
+use bevy_reflect::{Reflect, TypePath};
+
+fn overlay_system() {}
+
+#[derive(Reflect, Default)]
+struct OverlayPlugin;
+
+gen_plugin! {
+    impl for OverlayPlugin;
+    toggle(enabled = true);
+    systems(bevy_app::Update)(overlay_system);
+}
+
+#[derive(GenPlugin, Reflect, Default)]
+#[gen_plugin(toggle(enabled = false);)]
+struct HudPlugin;
+
+#[test]
+fn reflected_plugins() {
+    let mut app = App::new();
+    app.add_plugins((OverlayPlugin, HudPlugin));
+
+    assert!(app.world.resource::<PluginEnabled<OverlayPlugin>>().enabled);
+    assert!(!app.world.resource::<PluginEnabled<HudPlugin>>().enabled);
+    assert_eq!(
+        PluginEnabled::<HudPlugin>::type_path(),
+        "bevy_denshi_ika_gen_plugin::PluginEnabled<toggle::HudPlugin>",
+    );
+    assert!(app
+        .world
+        .resource::<bevy_ecs::reflect::AppTypeRegistry>()
+        .read()
+        .get_with_short_type_path("PluginEnabled<OverlayPlugin>")
+        .is_some());
+}
@@ -0,0 +1,15 @@
+use bevy_denshi_ika_gen_plugin::gen_plugin;
+
+fn my_system() {}
+
+gen_plugin! {
+    MyPlugin;
+    name("my_plugin", "other_name");
+    systems(bevy_app::Update);
+    fixed(rate = 60.0)(my_system);
+    toggle(true);
+    systems(bevy_app::Update, chain = true)(my_system);
+    systems(bevy_app::Update)(my_system)
+}
+
+fn main() {}
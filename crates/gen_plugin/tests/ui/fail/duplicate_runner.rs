@@ -0,0 +1,21 @@
+use bevy_app::App;
+use bevy_denshi_ika_gen_plugin::gen_plugin;
+
+fn my_runner(_: App) {}
+
+gen_plugin! {
+    MyPlugin;
+    runner(my_runner);
+    runner(my_runner);
+}
+
+gen_plugin! {
+    MyWhenPlugin;
+    when(cfg(unix)) {
+        runner(my_runner);
+    }
+    #[cfg(unix)]
+    runner(my_runner);
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use bevy_denshi_ika_gen_plugin::gen_plugin;
+
+fn physics_system() {}
+
+gen_plugin! {
+    PhysicsPlugin;
+    fixed(hz = "60")(physics_system);
+}
+
+fn main() {}
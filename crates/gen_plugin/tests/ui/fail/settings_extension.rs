@@ -0,0 +1,11 @@
+use bevy_denshi_ika_gen_plugin::gen_plugin;
+
+#[derive(bevy_ecs::system::Resource, serde::Deserialize, Default)]
+struct CameraSettings;
+
+gen_plugin! {
+    CameraPlugin;
+    settings(CameraSettings = "settings/camera.ron");
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use bevy_denshi_ika_gen_plugin::gen_plugin;
+
+fn my_system() {}
+
+gen_plugin! {
+    MyPlugin;
+    system(bevy_app::Update)(my_system);
+    frobnicate(bevy_app::Update);
+}
+
+fn main() {}
@@ -0,0 +1,30 @@
+use bevy_app::App;
+use bevy_denshi_ika_gen_plugin::gen_plugin;
+
+fn unix_runner(_: App) {}
+
+fn other_runner(_: App) {}
+
+gen_plugin! {
+    RunnerPlugin;
+    #[cfg(unix)]
+    runner(unix_runner);
+    #[cfg(not(unix))]
+    runner(other_runner);
+}
+
+gen_plugin! {
+    WhenRunnerPlugin;
+    when(cfg(unix)) {
+        runner(unix_runner);
+        name("unix");
+    }
+    when(cfg(not(unix))) {
+        runner(other_runner);
+        name("other");
+    }
+}
+
+fn main() {
+    App::new().add_plugins((RunnerPlugin, WhenRunnerPlugin));
+}
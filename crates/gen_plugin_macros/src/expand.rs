@@ -0,0 +1,1815 @@
+use proc_macro2::{Delimiter, Group, Spacing, Span, TokenStream, TokenTree};
+use quote::{format_ident, quote, quote_spanned, ToTokens};
+use syn::{spanned::Spanned, Attribute, Expr, GenericParam, Generics, Ident, Type, Visibility};
+
+use crate::parse::{
+    DerivePlugin, GenPlugin, GroupInvocation, GroupSectionKind, Header, Invariant, Invocation,
+    MainOptions, PluginHeader, ReflectEntry, Section, SectionKind, SpawnEntity, SpawnSchedule,
+    StateEntry, SystemConfig,
+};
+
+/// Identifiers shared by the whole expansion
+struct Context {
+    /// Path to `bevy_denshi_ika_gen_plugin`
+    krate: TokenStream,
+    /// Path to the re-exports of `bevy_denshi_ika_gen_plugin`
+    private: TokenStream,
+    app: Ident,
+    /// Receiver of the methods of [`Plugin`], it takes the hygiene of the plugin name
+    /// so the sections can use it even when invoked through `gen_plugin!`
+    receiver: syn::Token![self],
+    /// Expression of the [`SystemSet`] containing the systems of the plugin
+    set: TokenStream,
+    /// `cfg!` of the `profile` section, if the plugin has one
+    profile: Option<TokenStream>,
+    /// Whether the plugin has `state_resources` sections, its other systems are
+    /// then ordered between the initialization and the removal of the resources
+    state_resources: bool,
+}
+
+#[derive(Default)]
+struct Methods {
+    build: Vec<TokenStream>,
+    finish: Vec<TokenStream>,
+    cleanup: Vec<TokenStream>,
+    /// `requires` and `conflicts` checks, run in [`Plugin::ready`] as the plugin
+    /// registry is not accessible from [`Plugin::finish`]
+    checks: Vec<TokenStream>,
+    /// `name` and `is_unique` methods of [`Plugin`]
+    overrides: Vec<TokenStream>,
+    /// Schedules the plugin adds systems or sets to, with the attributes of their section
+    schedules: Vec<(TokenStream, TokenStream)>,
+    /// `before` and `after` sections, with the attributes of their section
+    orderings: Vec<(TokenStream, Ident, Type)>,
+    /// Attributes of the `toggle` sections, one per `cfg` they are under
+    toggles: Vec<TokenStream>,
+}
+
+pub fn expand(invocation: Invocation) -> TokenStream {
+    let Invocation { krate, plugin } = invocation;
+
+    expand_plugin(krate.into_token_stream(), plugin)
+}
+
+/// The derive cannot receive `$crate`, the crate is expected under its own name
+/// unless the path is given with `#[gen_plugin(crate = path)]`
+pub fn expand_derive(derive: DerivePlugin) -> TokenStream {
+    let krate = match derive.krate {
+        Some(krate) => krate.into_token_stream(),
+        None => quote! { ::bevy_denshi_ika_gen_plugin },
+    };
+    expand_plugin(krate, derive.plugin)
+}
+
+fn expand_plugin(krate: TokenStream, plugin: GenPlugin) -> TokenStream {
+    let (vis, name, generics) = match &plugin.header {
+        Header::Main(_) => (
+            Visibility::Inherited,
+            Ident::new("MainPlugin", Span::mixed_site()),
+            Generics::default(),
+        ),
+        Header::Plugin(header) => (
+            header.vis.clone(),
+            header.name.clone(),
+            header.generics.clone(),
+        ),
+    };
+    let generics = with_static_type_params(&generics);
+    let set_name = format_ident!("{}Set", name);
+    let (_, type_generics, _) = generics.split_for_impl();
+    // The systems are profiled when one of the `profile` sections is enabled
+    let profile_cfgs = plugin
+        .sections
+        .iter()
+        .filter(|section| matches!(section.kind, SectionKind::Profile))
+        .map(|section| {
+            let cfgs = section
+                .attrs
+                .iter()
+                .filter(|attr| attr.path().is_ident("cfg"))
+                .filter_map(|attr| attr.meta.require_list().ok())
+                .map(|list| &list.tokens);
+            quote! { all(#(#cfgs),*) }
+        })
+        .collect::<Vec<TokenStream>>();
+    let profile =
+        (!profile_cfgs.is_empty()).then(|| quote! { ::core::cfg!(any(#(#profile_cfgs),*)) });
+
+    let context = Context {
+        private: quote! { #krate::__private },
+        app: Ident::new("app", Span::mixed_site()),
+        receiver: syn::Token![self](name.span()),
+        set: quote! { <#set_name #type_generics as ::core::default::Default>::default() },
+        profile,
+        state_resources: plugin
+            .sections
+            .iter()
+            .any(|section| matches!(section.kind, SectionKind::StateResources { .. })),
+        krate,
+    };
+
+    let mut methods = Methods::default();
+    let mut manifest = Vec::new();
+    for section in &plugin.sections {
+        expand_section(&context, section, &mut methods);
+        collect_manifest(&context, section, &mut manifest);
+    }
+    expand_plugin_set_configs(&context, &mut methods);
+
+    let system_set = expand_system_set(&context, &vis, &name, &set_name, &generics);
+    let manifest = expand_manifest(&context, &name, &generics, manifest);
+    let plugin_impl = expand_plugin_impl(&context, &name, &generics, methods);
+    let self_test = plugin
+        .sections
+        .iter()
+        .filter(|section| matches!(section.kind, SectionKind::SelfTest { .. }))
+        .map(|section| expand_self_test(&context, &plugin.header, section))
+        .collect::<Vec<TokenStream>>();
+    let dynamic = plugin
+        .sections
+        .iter()
+        .filter(|section| matches!(section.kind, SectionKind::Dynamic))
+        .map(|section| expand_dynamic(&context, &plugin.header, section))
+        .collect::<Vec<TokenStream>>();
+
+    match &plugin.header {
+        Header::Main(options) => {
+            let body = expand_main_body(&context, &name, options);
+
+            quote! {
+                fn main() -> ::std::process::ExitCode {
+                    struct #name;
+
+                    #system_set
+
+                    #manifest
+
+                    #plugin_impl
+
+                    #body
+                }
+
+                #(#self_test)*
+
+                #(#dynamic)*
+            }
+        }
+        Header::Plugin(header) => {
+            let definition = expand_definition(header);
+
+            quote! {
+                #definition
+
+                #system_set
+
+                #manifest
+
+                #plugin_impl
+
+                #(#self_test)*
+
+                #(#dynamic)*
+            }
+        }
+    }
+}
+
+pub fn expand_group(invocation: GroupInvocation) -> TokenStream {
+    let GroupInvocation { krate, group } = invocation;
+    let private = quote! { #krate::__private };
+    let builder = Ident::new("group", Span::mixed_site());
+
+    let header = PluginHeader {
+        generics: with_static_type_params(&group.header.generics),
+        ..group.header
+    };
+    let PluginHeader { name, generics, .. } = &header;
+    let (impl_generics, type_generics, where_clause) = generics.split_for_impl();
+    // `self` takes the hygiene of the group name like `Context::receiver`
+    let self_token = syn::Token![self](name.span());
+
+    let statements = group.sections.iter().map(|section| {
+        let attrs = statement_attrs(&section.attrs);
+        let statement = match &section.kind {
+            GroupSectionKind::Plugins(plugins) => quote! {
+                #(#builder = #builder.add(#plugins);)*
+            },
+            GroupSectionKind::Before { target, plugins } => quote! {
+                #(#builder = #builder.add_before::<#target, _>(#plugins);)*
+            },
+            // Added in reverse so the plugins keep their order after the target
+            GroupSectionKind::After { target, plugins } => {
+                let plugins = plugins.iter().rev();
+                quote! {
+                    #(#builder = #builder.add_after::<#target, _>(#plugins);)*
+                }
+            }
+            GroupSectionKind::Set(plugins) => quote! {
+                #(#builder = #builder.set(#plugins);)*
+            },
+            GroupSectionKind::Disable(plugins) => quote! {
+                #(#builder = #builder.disable::<#plugins>();)*
+            },
+        };
+
+        quote! { #attrs { #statement } }
+    });
+
+    let definition = expand_definition(&header);
+
+    quote! {
+        #definition
+
+        impl #impl_generics #private::bevy_app::PluginGroup for #name #type_generics #where_clause {
+            fn build(#self_token) -> #private::bevy_app::PluginGroupBuilder {
+                #[allow(unused_mut)]
+                let mut #builder = #private::bevy_app::PluginGroupBuilder::start::<Self>();
+                #(#statements)*
+                #builder
+            }
+        }
+    }
+}
+
+/// Body of the `main` function, builds the app with its options and runs it
+fn expand_main_body(context: &Context, name: &Ident, options: &MainOptions) -> TokenStream {
+    let Context { private, app, .. } = context;
+    let MainOptions {
+        headless,
+        frames,
+        args,
+        app: factory,
+    } = options;
+
+    let factory = match factory {
+        Some(factory) => quote! { (#factory)() },
+        None => quote! { #private::bevy_app::App::new() },
+    };
+    let args = args.as_ref().map(|args| {
+        quote! { #app.insert_resource((#args)(::std::env::args())); }
+    });
+    let frames = match frames {
+        Some(frames) => quote! { ::core::option::Option::Some(#frames) },
+        None => quote! { ::core::option::Option::None },
+    };
+
+    quote! {
+        let mut #app: #private::bevy_app::App = #factory;
+        #args
+        #app.add_plugins(#name);
+        #private::run_main(#app, #headless, #frames)
+    }
+}
+
+/// Test running the plugin headlessly, the plugin is built with its default fields
+fn expand_self_test(context: &Context, header: &Header, section: &Section) -> TokenStream {
+    let private = &context.private;
+    let SectionKind::SelfTest {
+        frames,
+        setup,
+        plugins,
+    } = &section.kind
+    else {
+        unreachable!("only called on `self_test` sections");
+    };
+
+    let header = match header {
+        Header::Main(_) => {
+            return syn::Error::new(
+                section.name.span(),
+                "`self_test` is not supported in `main`, the test would need a window",
+            )
+            .to_compile_error()
+        }
+        Header::Plugin(header) => header,
+    };
+    if !header.generics.params.is_empty() {
+        return syn::Error::new(
+            section.name.span(),
+            "`self_test` is not supported on generic plugins, \
+            use a concrete instance in the plugins of another `self_test`",
+        )
+        .to_compile_error();
+    }
+
+    let name = &header.name;
+    let plugin = match header.fields.is_empty() && !header.existing {
+        true => quote! { #name },
+        false => quote! { <#name as ::core::default::Default>::default() },
+    };
+    let attrs = statement_attrs(&section.attrs);
+    let app = Ident::new("app", Span::mixed_site());
+    let setup = setup.as_ref().map(|setup| quote! { (#setup)(&mut #app); });
+    let test = Ident::new(&format!("{}_self_test", snake_case(name)), name.span());
+
+    quote! {
+        #attrs
+        #[cfg(test)]
+        #[test]
+        fn #test() {
+            let mut #app = #private::self_test_app();
+            #setup
+            #app.add_plugins((#(#plugins,)* #plugin,));
+            #private::run_self_test::<#name>(&mut #app, #frames);
+        }
+    }
+}
+
+/// Entry point of the plugin in a dynamic library, loaded by `dynamically_load_plugin`,
+/// the plugin is built with its default fields
+fn expand_dynamic(context: &Context, header: &Header, section: &Section) -> TokenStream {
+    let private = &context.private;
+
+    let header = match header {
+        Header::Main(_) => {
+            return syn::Error::new(
+                section.name.span(),
+                "`dynamic` is not supported in `main`, the plugin has to be loaded by an app",
+            )
+            .to_compile_error()
+        }
+        Header::Plugin(header) => header,
+    };
+    if !header.generics.params.is_empty() {
+        return syn::Error::new(
+            section.name.span(),
+            "`dynamic` is not supported on generic plugins, \
+            a dynamic library exports a single concrete plugin",
+        )
+        .to_compile_error();
+    }
+
+    let name = &header.name;
+    let plugin = match header.fields.is_empty() && !header.existing {
+        true => quote! { #name },
+        false => quote! { <#name as ::core::default::Default>::default() },
+    };
+    let attrs = statement_attrs(&section.attrs);
+
+    // Same signature as `CreatePlugin`, the type `dynamically_load_plugin` calls it with
+    quote! {
+        #attrs
+        #[no_mangle]
+        pub fn _bevy_create_plugin() -> *mut dyn #private::bevy_app::Plugin {
+            ::std::boxed::Box::into_raw(::std::boxed::Box::new(#plugin))
+        }
+    }
+}
+
+/// Converts an `UpperCamelCase` identifier to `snake_case`
+fn snake_case(ident: &Ident) -> String {
+    let mut snake = String::new();
+    for (i, char) in ident.to_string().chars().enumerate() {
+        if char.is_uppercase() && i != 0 {
+            snake.push('_');
+        }
+        snake.extend(char.to_lowercase());
+    }
+    snake
+}
+
+/// Adds `'static` bounds on the type parameters, needed for [`Plugin`] and [`SystemSet`]
+fn with_static_type_params(generics: &Generics) -> Generics {
+    let mut generics = generics.clone();
+    let type_params = generics
+        .type_params()
+        .map(|param| param.ident.clone())
+        .collect::<Vec<Ident>>();
+    let where_clause = generics.make_where_clause();
+    for param in type_params {
+        where_clause
+            .predicates
+            .push(syn::parse_quote! { #param: 'static });
+    }
+    generics
+}
+
+/// Types to put in a [`PhantomData`] to use every generic parameter
+fn marker_types(generics: &Generics) -> Vec<TokenStream> {
+    generics
+        .params
+        .iter()
+        .filter_map(|param| match param {
+            GenericParam::Lifetime(param) => {
+                let lifetime = &param.lifetime;
+                Some(quote! { &#lifetime () })
+            }
+            GenericParam::Type(param) => Some(param.ident.to_token_stream()),
+            GenericParam::Const(_) => None,
+        })
+        .collect()
+}
+
+fn expand_definition(header: &PluginHeader) -> TokenStream {
+    let PluginHeader {
+        vis,
+        name,
+        generics,
+        fields,
+        existing,
+    } = header;
+
+    if *existing {
+        return TokenStream::new();
+    }
+
+    if generics.params.is_empty() && fields.is_empty() {
+        return quote! { #vis struct #name; };
+    }
+
+    let (impl_generics, type_generics, where_clause) = generics.split_for_impl();
+
+    let marker_types = marker_types(generics);
+
+    let (marker_field, marker_default) = match marker_types.is_empty() {
+        true => (None, None),
+        false => (
+            Some(quote! { _marker: ::core::marker::PhantomData<fn() -> (#(#marker_types,)*)>, }),
+            Some(quote! { _marker: ::core::marker::PhantomData, }),
+        ),
+    };
+
+    let field_definitions = fields.iter().map(|field| {
+        let (attrs, vis, name, ty) = (&field.attrs, &field.vis, &field.name, &field.ty);
+        quote! { #(#attrs)* #vis #name: #ty, }
+    });
+
+    let field_defaults = fields.iter().map(|field| {
+        let name = &field.name;
+        match &field.default {
+            Some(default) => quote! { #name: #default, },
+            None => quote! { #name: ::core::default::Default::default(), },
+        }
+    });
+
+    let setters = fields.iter().map(|field| {
+        let (name, ty) = (&field.name, &field.ty);
+        let doc = format!("Sets `{name}`");
+        quote! {
+            #[doc = #doc]
+            #[must_use]
+            #vis fn #name(mut self, #name: #ty) -> Self {
+                self.#name = #name;
+                self
+            }
+        }
+    });
+
+    quote! {
+        #vis struct #name #generics #where_clause {
+            #(#field_definitions)*
+            #marker_field
+        }
+
+        impl #impl_generics ::core::default::Default for #name #type_generics #where_clause {
+            fn default() -> Self {
+                Self {
+                    #(#field_defaults)*
+                    #marker_default
+                }
+            }
+        }
+
+        impl #impl_generics #name #type_generics #where_clause {
+            #(#setters)*
+        }
+    }
+}
+
+fn expand_system_set(
+    context: &Context,
+    vis: &Visibility,
+    name: &Ident,
+    set_name: &Ident,
+    generics: &Generics,
+) -> TokenStream {
+    let Context { krate, private, .. } = context;
+    let (impl_generics, type_generics, where_clause) = generics.split_for_impl();
+
+    let marker_types = marker_types(generics);
+    let (fields, constructor) = match marker_types.is_empty() {
+        true => (None, quote! { Self }),
+        false => (
+            Some(quote! { (::core::marker::PhantomData<fn() -> (#(#marker_types,)*)>) }),
+            quote! { Self(::core::marker::PhantomData) },
+        ),
+    };
+
+    let doc = format!("System set containing the systems and sub-sets of [`{name}`]");
+    let set_name_string = set_name.to_string();
+
+    // The traits are implemented by hand as derives would add bounds on the generic parameters
+    quote! {
+        #[doc = #doc]
+        #vis struct #set_name #generics #fields #where_clause;
+
+        impl #impl_generics ::core::clone::Clone for #set_name #type_generics #where_clause {
+            fn clone(&self) -> Self {
+                *self
+            }
+        }
+
+        impl #impl_generics ::core::marker::Copy for #set_name #type_generics #where_clause {}
+
+        impl #impl_generics ::core::default::Default for #set_name #type_generics #where_clause {
+            fn default() -> Self {
+                #constructor
+            }
+        }
+
+        impl #impl_generics ::core::fmt::Debug for #set_name #type_generics #where_clause {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                f.write_str(#set_name_string)
+            }
+        }
+
+        impl #impl_generics ::core::cmp::PartialEq for #set_name #type_generics #where_clause {
+            fn eq(&self, _: &Self) -> bool {
+                true
+            }
+        }
+
+        impl #impl_generics ::core::cmp::Eq for #set_name #type_generics #where_clause {}
+
+        impl #impl_generics ::core::hash::Hash for #set_name #type_generics #where_clause {
+            fn hash<H: ::core::hash::Hasher>(&self, _: &mut H) {}
+        }
+
+        impl #impl_generics #private::bevy_ecs::schedule::SystemSet
+            for #set_name #type_generics #where_clause
+        {
+            fn dyn_clone(&self) -> ::std::boxed::Box<dyn #private::bevy_ecs::schedule::SystemSet> {
+                ::std::boxed::Box::new(*self)
+            }
+
+            fn as_dyn_eq(&self) -> &dyn #private::bevy_utils::label::DynEq {
+                self
+            }
+
+            fn dyn_hash(&self, mut state: &mut dyn ::core::hash::Hasher) {
+                ::core::hash::Hash::hash(&::core::any::TypeId::of::<Self>(), &mut state);
+            }
+        }
+
+        impl #impl_generics #krate::PluginSystemSet for #name #type_generics #where_clause {
+            type SystemSet = #set_name #type_generics;
+        }
+    }
+}
+
+fn expand_plugin_impl(
+    context: &Context,
+    name: &Ident,
+    generics: &Generics,
+    methods: Methods,
+) -> TokenStream {
+    let Context {
+        krate,
+        private,
+        app,
+        receiver,
+        ..
+    } = context;
+    let (impl_generics, type_generics, where_clause) = generics.split_for_impl();
+    let Methods {
+        build,
+        finish,
+        cleanup,
+        checks,
+        overrides,
+        ..
+    } = methods;
+
+    let ready = (!checks.is_empty()).then(|| {
+        quote! {
+            fn ready(&#receiver, #app: &#private::bevy_app::App) -> bool {
+                #(#checks)*
+                true
+            }
+        }
+    });
+
+    quote! {
+        impl #impl_generics #private::bevy_app::Plugin for #name #type_generics #where_clause {
+            #ready
+
+            fn build(&#receiver, #app: &mut #private::bevy_app::App) {
+                #private::register_manifest(#app, #krate::GeneratedPlugin::manifest(#receiver));
+                #(#build)*
+            }
+
+            fn finish(&#receiver, #app: &mut #private::bevy_app::App) {
+                #(#finish)*
+            }
+
+            fn cleanup(&#receiver, #app: &mut #private::bevy_app::App) {
+                #(#cleanup)*
+            }
+
+            #(#overrides)*
+        }
+    }
+}
+
+fn expand_manifest(
+    context: &Context,
+    name: &Ident,
+    generics: &Generics,
+    statements: Vec<TokenStream>,
+) -> TokenStream {
+    let Context {
+        krate, receiver, ..
+    } = context;
+    let (impl_generics, type_generics, where_clause) = generics.split_for_impl();
+    let manifest = Ident::new("manifest", Span::mixed_site());
+    let name_string = name.to_string();
+
+    quote! {
+        impl #impl_generics #krate::GeneratedPlugin for #name #type_generics #where_clause {
+            fn manifest(&#receiver) -> #krate::PluginManifest {
+                #[allow(unused_mut)]
+                let mut #manifest = #krate::PluginManifest {
+                    name: #name_string,
+                    ..::core::default::Default::default()
+                };
+                #(#statements)*
+                #manifest
+            }
+        }
+    }
+}
+
+/// Adds the type names of the items of the section to the manifest, the expressions
+/// are not evaluated, the systems of tuples and of [`IntoSystemConfigs`] methods are
+/// added individually
+fn collect_manifest(context: &Context, section: &Section, statements: &mut Vec<TokenStream>) {
+    let Context { krate, private, .. } = context;
+    let manifest = Ident::new("manifest", Span::mixed_site());
+    let attrs = statement_attrs(&section.attrs);
+
+    let type_names = |types: &mut dyn Iterator<Item = &Type>| {
+        types
+            .map(|ty| quote! { ::core::any::type_name::<#ty>() })
+            .collect::<Vec<TokenStream>>()
+    };
+    let value_type_names = |values: &[Expr]| {
+        values
+            .iter()
+            .map(|value| quote! { #private::type_name_of_returned(|| #value) })
+            .collect::<Vec<TokenStream>>()
+    };
+    let systems_manifest = |schedule: TokenStream, systems: &[Expr]| {
+        let mut leaves = Vec::new();
+        for system in systems {
+            system_leaves(system, &mut leaves);
+        }
+        quote! {
+            #krate::SystemsManifest {
+                schedule: #schedule,
+                systems: ::std::vec![#(#private::type_name_of_returned(|| #leaves)),*],
+            }
+        }
+    };
+
+    let (field, items) = match &section.kind {
+        SectionKind::Plugins(plugins) => ("plugins", value_type_names(plugins)),
+        SectionKind::Reflect(entries) => (
+            "reflect",
+            type_names(&mut entries.iter().map(|entry| &entry.ty)),
+        ),
+        SectionKind::Events(events) => ("events", type_names(&mut events.iter())),
+        SectionKind::Resources(resources) | SectionKind::NonSendResources(resources) => {
+            ("resources", value_type_names(resources))
+        }
+        SectionKind::InitResources(resources)
+        | SectionKind::InitNonSendResources(resources)
+        | SectionKind::StateResources { resources, .. } => {
+            ("resources", type_names(&mut resources.iter()))
+        }
+        SectionKind::Settings(entries) => (
+            "resources",
+            type_names(&mut entries.iter().map(|entry| &entry.ty)),
+        ),
+        SectionKind::States(states) => (
+            "states",
+            type_names(&mut states.iter().map(|state| &state.ty)),
+        ),
+        SectionKind::Systems {
+            schedule, systems, ..
+        } => (
+            "systems",
+            vec![systems_manifest(
+                quote! { ::core::stringify!(#schedule) },
+                systems,
+            )],
+        ),
+        SectionKind::Fixed { systems, .. } => (
+            "systems",
+            vec![systems_manifest(quote! { "FixedUpdate" }, systems)],
+        ),
+        SectionKind::OnEvent {
+            event,
+            schedule,
+            handlers,
+            ..
+        } => {
+            statements.push(quote! {
+                #attrs #manifest.events.push(::core::any::type_name::<#event>());
+            });
+            (
+                "systems",
+                vec![systems_manifest(
+                    quote! { ::core::stringify!(#schedule) },
+                    handlers,
+                )],
+            )
+        }
+        SectionKind::OnEnter { state, systems } => (
+            "systems",
+            vec![systems_manifest(
+                quote! { ::core::concat!("OnEnter(", ::core::stringify!(#state), ")") },
+                systems,
+            )],
+        ),
+        SectionKind::OnExit { state, systems } => (
+            "systems",
+            vec![systems_manifest(
+                quote! { ::core::concat!("OnExit(", ::core::stringify!(#state), ")") },
+                systems,
+            )],
+        ),
+        SectionKind::OnTransition { from, to, systems } => (
+            "systems",
+            vec![systems_manifest(
+                quote! {
+                    ::core::concat!(
+                        "OnTransition { from: ",
+                        ::core::stringify!(#from),
+                        ", to: ",
+                        ::core::stringify!(#to),
+                        " }",
+                    )
+                },
+                systems,
+            )],
+        ),
+        _ => return,
+    };
+
+    let field = Ident::new(field, Span::call_site());
+    statements.extend(
+        items
+            .into_iter()
+            .map(|item| quote! { #attrs #manifest.#field.push(#item); }),
+    );
+}
+
+/// Orders the set of the plugin and runs it only while its `PluginEnabled` resource
+/// is enabled, in every schedule it adds systems or sets to. The schedules are
+/// deduplicated once interned as different paths can name the same schedule
+/// (e.g. `Update` and `bevy::app::Update`)
+fn expand_plugin_set_configs(context: &Context, methods: &mut Methods) {
+    let Context {
+        krate,
+        private,
+        app,
+        set,
+        ..
+    } = context;
+    if methods.orderings.is_empty() && methods.toggles.is_empty() {
+        return;
+    }
+
+    let schedules = Ident::new("schedules", Span::mixed_site());
+    let schedule = Ident::new("schedule", Span::mixed_site());
+
+    let pushes = methods.schedules.iter().map(|(schedule_attrs, label)| {
+        quote! {
+            #schedule_attrs
+            #private::push_schedule(&mut #schedules, #label);
+        }
+    });
+    let orderings = methods
+        .orderings
+        .iter()
+        .map(|(ordering_attrs, method, plugin)| {
+            quote! {
+                #ordering_attrs
+                #app.configure_sets(
+                    #schedule,
+                    #private::bevy_ecs::schedule::IntoSystemSetConfigs::#method(
+                        #set,
+                        <<#plugin as #krate::PluginSystemSet>::SystemSet
+                            as ::core::default::Default>::default(),
+                    ),
+                );
+            }
+        });
+    let toggles = methods.toggles.iter().map(|toggle_attrs| {
+        quote! {
+            #toggle_attrs
+            #app.configure_sets(
+                #schedule,
+                #private::bevy_ecs::schedule::IntoSystemSetConfigs::run_if(
+                    #set,
+                    #private::plugin_enabled::<Self>,
+                ),
+            );
+        }
+    });
+
+    methods.build.push(quote! {
+        {
+            #[allow(unused_mut)]
+            let mut #schedules =
+                ::std::vec::Vec::<#private::bevy_ecs::schedule::InternedScheduleLabel>::new();
+            #(#pushes)*
+            for #schedule in #schedules {
+                #(#orderings)*
+                #(#toggles)*
+            }
+        }
+    });
+}
+
+/// [`IntoSystemConfigs`] methods looked through to profile the systems they configure
+const SYSTEM_CONFIG_METHODS: &[&str] = &[
+    "before",
+    "after",
+    "before_ignore_deferred",
+    "after_ignore_deferred",
+    "in_set",
+    "run_if",
+    "distributive_run_if",
+    "ambiguous_with",
+    "ambiguous_with_all",
+    "chain",
+    "chain_ignore_deferred",
+];
+
+/// Wraps every system of the list if the plugin has a `profile` section
+fn profile_systems(context: &Context, systems: &[Expr]) -> TokenStream {
+    let systems = systems
+        .iter()
+        .map(|system| profile_system_expr(context, system));
+    quote! { (#(#systems),*) }
+}
+
+/// Wraps the system if the plugin has a `profile` section, the elements of tuples
+/// and the receivers of [`IntoSystemConfigs`] methods are wrapped individually
+fn profile_system_expr(context: &Context, system: &Expr) -> TokenStream {
+    if context.profile.is_none() {
+        return system.to_token_stream();
+    }
+
+    match system {
+        Expr::Tuple(tuple) => {
+            let elems = tuple
+                .elems
+                .iter()
+                .map(|elem| profile_system_expr(context, elem));
+            quote! { (#(#elems,)*) }
+        }
+        Expr::Paren(paren) => profile_system_expr(context, &paren.expr),
+        Expr::MethodCall(call)
+            if SYSTEM_CONFIG_METHODS.contains(&call.method.to_string().as_str()) =>
+        {
+            let receiver = profile_system_expr(context, &call.receiver);
+            let syn::ExprMethodCall {
+                attrs,
+                method,
+                turbofish,
+                args,
+                ..
+            } = call;
+            quote! { #(#attrs)* #receiver.#method #turbofish(#args) }
+        }
+        _ => profile_system(context, system.to_token_stream()),
+    }
+}
+
+/// Systems of the expression, the elements of tuples and the receivers
+/// of [`IntoSystemConfigs`] methods are looked through
+fn system_leaves<'a>(system: &'a Expr, leaves: &mut Vec<&'a Expr>) {
+    match system {
+        Expr::Tuple(tuple) => {
+            for elem in &tuple.elems {
+                system_leaves(elem, leaves);
+            }
+        }
+        Expr::Paren(paren) => system_leaves(&paren.expr, leaves),
+        Expr::MethodCall(call)
+            if SYSTEM_CONFIG_METHODS.contains(&call.method.to_string().as_str()) =>
+        {
+            system_leaves(&call.receiver, leaves);
+        }
+        _ => leaves.push(system),
+    }
+}
+
+/// Wraps the system in a `ProfiledSystem` if the plugin has a `profile` section
+fn profile_system(context: &Context, system: TokenStream) -> TokenStream {
+    let Context {
+        private, profile, ..
+    } = context;
+    let Some(enabled) = profile else {
+        return system;
+    };
+
+    quote_spanned! {system.span()=>
+        #private::profile_system(#system, ::core::any::type_name::<Self>(), #enabled)
+    }
+}
+
+/// Applies the configurations as [`IntoSystemConfigs`] methods in order
+fn apply_system_configs(
+    context: &Context,
+    systems: TokenStream,
+    configs: &[SystemConfig],
+) -> TokenStream {
+    let private = &context.private;
+
+    configs
+        .iter()
+        .fold(systems, |systems, SystemConfig { name, value }| {
+            match (name.to_string().as_str(), value) {
+                ("in_state", Some(state)) => quote! {
+                    #private::bevy_ecs::schedule::IntoSystemConfigs::run_if(
+                        #systems,
+                        #private::bevy_ecs::schedule::common_conditions::in_state(#state),
+                    )
+                },
+                (_, Some(value)) => quote! {
+                    #private::bevy_ecs::schedule::IntoSystemConfigs::#name(#systems, #value)
+                },
+                (_, None) => quote! {
+                    #private::bevy_ecs::schedule::IntoSystemConfigs::#name(#systems)
+                },
+            }
+        })
+}
+
+/// System checking the invariants of the entities matching the filter, it uses
+/// the `plugin` and `severity` variables declared by the `invariants` section
+fn expand_invariants(context: &Context, filter: &Type, invariants: &[Invariant]) -> TokenStream {
+    let Context { krate, private, .. } = context;
+    let ident = |name: &str| Ident::new(name, Span::mixed_site());
+    let (query, removed, relations, tracker, events) = (
+        ident("query"),
+        ident("removed"),
+        ident("relations"),
+        ident("tracker"),
+        ident("events"),
+    );
+    let (entity, debug_name, plugin, severity, self_test) = (
+        ident("entity"),
+        ident("debug_name"),
+        ident("plugin"),
+        ident("severity"),
+        ident("self_test"),
+    );
+    let parent = quote! { #private::bevy_hierarchy::Parent };
+    let children = quote! { #private::bevy_hierarchy::Children };
+
+    // Components whose addition, change or removal triggers a check
+    let mut watched = Vec::<TokenStream>::new();
+    let mut watch = |component: TokenStream| {
+        let position = watched
+            .iter()
+            .position(|watched| watched.to_string() == component.to_string());
+        let index = position.unwrap_or_else(|| {
+            watched.push(component);
+            watched.len() - 1
+        });
+        ident(&format!("watched_{index}"))
+    };
+    // Components checked on the parent or the children with a `Query<Has<T>>` each
+    let mut relation_types = Vec::<Type>::new();
+    let mut relation = |component: &Type| {
+        relation_types.push(component.clone());
+        syn::Index::from(relation_types.len() - 1)
+    };
+
+    // Descriptions and conditions of the invariants
+    let mut checks = Vec::<(TokenStream, TokenStream)>::new();
+    for invariant in invariants {
+        match invariant {
+            Invariant::Has(components) => {
+                for component in components {
+                    let watched = watch(quote! { #component });
+                    checks.push((
+                        quote! { ::core::concat!("have `", ::core::stringify!(#component), "`") },
+                        quote! { #watched.is_some() },
+                    ));
+                }
+            }
+            Invariant::NotHas(components) => {
+                for component in components {
+                    let watched = watch(quote! { #component });
+                    checks.push((
+                        quote! {
+                            ::core::concat!("not have `", ::core::stringify!(#component), "`")
+                        },
+                        quote! { #watched.is_none() },
+                    ));
+                }
+            }
+            Invariant::Check {
+                component,
+                predicate,
+            } => {
+                let watched = watch(quote! { #component });
+                checks.push((
+                    quote! {
+                        ::core::concat!(
+                            "have `",
+                            ::core::stringify!(#component),
+                            "` satisfying `",
+                            ::core::stringify!(#predicate),
+                            "`",
+                        )
+                    },
+                    quote! {
+                        #watched.as_ref().map_or(true, |component| {
+                            #private::check_invariant(&**component, #predicate)
+                        })
+                    },
+                ));
+            }
+            Invariant::Parent(components) => {
+                let watched = watch(parent.clone());
+                for component in components {
+                    let index = relation(component);
+                    checks.push((
+                        quote! {
+                            ::core::concat!(
+                                "have a parent with `",
+                                ::core::stringify!(#component),
+                                "`",
+                            )
+                        },
+                        quote! {
+                            #watched.as_ref().map_or(false, |parent| {
+                                #relations.#index.get(parent.get()).unwrap_or(false)
+                            })
+                        },
+                    ));
+                }
+            }
+            Invariant::Children(components) => {
+                let watched = watch(children.clone());
+                for component in components {
+                    let index = relation(component);
+                    checks.push((
+                        quote! {
+                            ::core::concat!(
+                                "have children, all with `",
+                                ::core::stringify!(#component),
+                                "`",
+                            )
+                        },
+                        quote! {
+                            #watched.as_ref().map_or(false, |children| {
+                                children
+                                    .iter()
+                                    .all(|child| #relations.#index.get(*child).unwrap_or(false))
+                            })
+                        },
+                    ));
+                }
+            }
+        }
+    }
+
+    let watched_idents = (0..watched.len())
+        .map(|index| ident(&format!("watched_{index}")))
+        .collect::<Vec<Ident>>();
+    let removed_indices = (0..watched.len()).map(syn::Index::from);
+    let removed_mut = (!watched.is_empty()).then(|| quote! { mut });
+    let checks = checks
+        .into_iter()
+        .enumerate()
+        .map(|(index, (description, condition))| {
+            quote! {
+                if #tracker.record(#entity, #index, #condition) {
+                    #private::report_invariant(
+                        #severity,
+                        #krate::InvariantViolated {
+                            entity: #entity,
+                            plugin: #plugin,
+                            invariant: #description,
+                        },
+                        &#debug_name,
+                        &mut #events,
+                    );
+                }
+            }
+        });
+
+    let system = quote! { #private::bevy_ecs::system };
+    let ref_ = quote! { #private::bevy_ecs::change_detection::Ref };
+    let removed_components = quote! { #private::bevy_ecs::removal_detection::RemovedComponents };
+
+    quote! {
+        move |#query: #private::bevy_ecs::system::Query<
+            (
+                #private::bevy_ecs::entity::Entity,
+                #private::bevy_core::DebugName,
+                (#(::core::option::Option<#ref_<#watched>>,)*),
+            ),
+            #filter,
+        >,
+              #removed_mut #removed: (#(#removed_components<#watched>,)*),
+              #relations: (#(#system::Query<#private::bevy_ecs::query::Has<#relation_types>>,)*),
+              mut #tracker: #system::Local<#private::InvariantTracker>,
+              mut #events: #private::bevy_ecs::event::EventWriter<#krate::InvariantViolated>,
+              #self_test: ::core::option::Option<#system::Res<#private::SelfTest>>| {
+            // Violations fail the tests generated by `self_test` sections
+            let #severity = match #self_test {
+                ::core::option::Option::Some(_) => #krate::InvariantSeverity::Panic,
+                ::core::option::Option::None => #severity,
+            };
+            #(#tracker.removed(#removed.#removed_indices.read());)*
+
+            for (#entity, #debug_name, (#(#watched_idents,)*)) in &#query {
+                let changed = false #(
+                    || #watched_idents.as_ref().is_some_and(|component| {
+                        #private::bevy_ecs::change_detection::DetectChanges::is_changed(component)
+                    })
+                )*;
+                if !#tracker.should_check(#entity, changed) {
+                    continue;
+                }
+
+                #(#checks)*
+            }
+
+            #tracker.finish();
+        }
+    }
+}
+
+impl Methods {
+    /// Adds the systems of the section to the schedule in the set of the plugin,
+    /// with the run conditions of the enclosing `when` blocks
+    fn add_systems(
+        &mut self,
+        context: &Context,
+        section: &Section,
+        schedule: TokenStream,
+        systems: TokenStream,
+    ) -> TokenStream {
+        let Context {
+            private, app, set, ..
+        } = context;
+
+        self.schedules
+            .push((statement_attrs(&section.attrs), schedule.clone()));
+
+        let systems = if context.state_resources
+            && !matches!(section.kind, SectionKind::StateResources { .. })
+        {
+            let plugin = quote! { ::core::any::TypeId::of::<Self>() };
+            quote! {
+                #private::bevy_ecs::schedule::IntoSystemConfigs::before(
+                    #private::bevy_ecs::schedule::IntoSystemConfigs::after(
+                        #systems,
+                        #private::StateResourcesSet::Init(#plugin),
+                    ),
+                    #private::StateResourcesSet::Remove(#plugin),
+                )
+            }
+        } else {
+            systems
+        };
+        let systems = section.run_if.iter().fold(systems, |systems, condition| {
+            quote! { #private::bevy_ecs::schedule::IntoSystemConfigs::run_if(#systems, #condition) }
+        });
+
+        quote! {
+            #app.add_systems(
+                #schedule,
+                #private::bevy_ecs::schedule::IntoSystemConfigs::in_set(#systems, #set),
+            );
+        }
+    }
+}
+
+/// Registers the type and its type data, the type data of bevy is found from the name
+/// of its trait, other type data is found in scope like `#[reflect(...)]` does
+fn expand_reflect(context: &Context, entry: &ReflectEntry) -> TokenStream {
+    let Context { private, app, .. } = context;
+    let ReflectEntry { ty, data } = entry;
+
+    let data = data.iter().map(|name| {
+        let (type_data, bounds) = match name.to_string().as_str() {
+            "Component" => (
+                quote! { #private::bevy_ecs::reflect::ReflectComponent },
+                Some(quote! {
+                    #private::bevy_ecs::component::Component
+                        + #private::bevy_ecs::world::FromWorld
+                }),
+            ),
+            "Resource" => (
+                quote! { #private::bevy_ecs::reflect::ReflectResource },
+                Some(quote! {
+                    #private::bevy_ecs::system::Resource + #private::bevy_ecs::world::FromWorld
+                }),
+            ),
+            "MapEntities" => (
+                quote! { #private::bevy_ecs::reflect::ReflectMapEntities },
+                Some(quote! {
+                    #private::bevy_ecs::component::Component
+                        + #private::bevy_ecs::entity::MapEntities
+                }),
+            ),
+            "Default" => (
+                quote! { #private::bevy_reflect::std_traits::ReflectDefault },
+                Some(quote! { ::core::default::Default }),
+            ),
+            "FromReflect" => (
+                quote! { #private::bevy_reflect::ReflectFromReflect },
+                Some(quote! { #private::bevy_reflect::FromReflect }),
+            ),
+            "Serialize" => (
+                quote! { #private::bevy_reflect::ReflectSerialize },
+                Some(quote! { #private::serde::Serialize }),
+            ),
+            "Deserialize" => (
+                quote! { #private::bevy_reflect::ReflectDeserialize },
+                Some(quote! { #private::serde::de::DeserializeOwned }),
+            ),
+            _ => {
+                let type_data = format_ident!("Reflect{}", name);
+                (quote! { #type_data }, None)
+            }
+        };
+
+        match bounds {
+            // Registered from a function bounded by the traits, an unsatisfied trait is
+            // reported on the invocation instead of as a missing `FromType` implementation
+            Some(bounds) => {
+                let register = Ident::new("register_type_data", Span::mixed_site());
+                quote_spanned! {name.span()=>
+                    {
+                        fn #register<T>(#app: &mut #private::bevy_app::App)
+                        where
+                            T: #private::bevy_reflect::Reflect
+                                + #private::bevy_reflect::TypePath
+                                + #bounds,
+                        {
+                            #app.register_type_data::<T, #type_data>();
+                        }
+                        #register::<#ty>(#app);
+                    }
+                }
+            }
+            None => quote! { #app.register_type_data::<#ty, #type_data>(); },
+        }
+    });
+
+    quote! {
+        #app.register_type::<#ty>();
+        #(#data)*
+    }
+}
+
+/// System spawning the entities of a `spawn` section through a command
+fn expand_spawn(context: &Context, entities: &[SpawnEntity]) -> TokenStream {
+    let private = &context.private;
+    let world = Ident::new("world", Span::mixed_site());
+
+    let entities = entities
+        .iter()
+        .map(|entity| expand_spawn_entity(context, &world, entity, None, 0));
+
+    quote! {
+        #private::spawn_system(
+            ::core::any::type_name::<Self>(),
+            |#world: &mut #private::bevy_ecs::world::World| {
+                #(#entities)*
+            },
+        )
+    }
+}
+
+/// Spawns the entity as a child of `parent` then its children, the bundle is built
+/// before spawning as the `asset(...)` calls of its components use the world
+fn expand_spawn_entity(
+    context: &Context,
+    world: &Ident,
+    entity: &SpawnEntity,
+    parent: Option<&Ident>,
+    depth: usize,
+) -> TokenStream {
+    let private = &context.private;
+    let bundle = Ident::new("bundle", Span::mixed_site());
+    let id = Ident::new(&format!("entity_{depth}"), Span::mixed_site());
+
+    let components = entity
+        .name
+        .iter()
+        .map(|name| quote! { #private::bevy_core::Name::new(#name) })
+        .chain(
+            entity
+                .components
+                .iter()
+                .map(|component| replace_asset_calls(context, world, component.to_token_stream())),
+        )
+        .collect::<Vec<TokenStream>>();
+    // Tuples of bundles are bundles, nesting them lifts the limit of 15 elements
+    let bundles = components
+        .chunks(15)
+        .map(|components| quote! { (#(#components,)*) });
+
+    let add_child = parent.map(|parent| {
+        quote! {
+            #private::bevy_hierarchy::BuildWorldChildren::add_child(
+                &mut #world.entity_mut(#parent),
+                #id,
+            );
+        }
+    });
+    let children = entity
+        .children
+        .iter()
+        .map(|child| expand_spawn_entity(context, world, child, Some(&id), depth + 1));
+
+    quote! {
+        {
+            let #bundle = (#(#bundles,)*);
+            let #id = #world.spawn(#bundle).id();
+            #add_child
+            #(#children)*
+        }
+    }
+}
+
+/// Replaces the `asset(...)` calls of the tokens by the addition of their
+/// argument to the [`Assets`] of the handle expected in their place
+fn replace_asset_calls(context: &Context, world: &Ident, tokens: TokenStream) -> TokenStream {
+    let private = &context.private;
+
+    let mut replaced = TokenStream::new();
+    let mut tokens = tokens.into_iter().peekable();
+    // `asset` is a method or an item of a path after `.` or `::`
+    let mut after_path = false;
+    let mut after_colon = false;
+    while let Some(token) = tokens.next() {
+        let (is_path, is_colon) = match &token {
+            TokenTree::Punct(punct) => (
+                punct.as_char() == '.' || (punct.as_char() == ':' && after_colon),
+                punct.as_char() == ':' && punct.spacing() == Spacing::Joint,
+            ),
+            _ => (false, false),
+        };
+
+        match token {
+            TokenTree::Ident(ident)
+                if ident == "asset"
+                    && !after_path
+                    && matches!(
+                        tokens.peek(),
+                        Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Parenthesis
+                    ) =>
+            {
+                let Some(TokenTree::Group(args)) = tokens.next() else {
+                    unreachable!("the next token is checked to be parentheses");
+                };
+                let args = replace_asset_calls(context, world, args.stream());
+                replaced.extend(quote_spanned! {ident.span()=>
+                    #private::spawn_asset(#world, #args)
+                });
+            }
+            TokenTree::Group(group) => {
+                let mut new_group = Group::new(
+                    group.delimiter(),
+                    replace_asset_calls(context, world, group.stream()),
+                );
+                new_group.set_span(group.span());
+                replaced.extend([TokenTree::Group(new_group)]);
+            }
+            token => replaced.extend([token]),
+        }
+
+        after_path = is_path;
+        after_colon = is_colon;
+    }
+
+    replaced
+}
+
+/// Attributes of a section to put on its statements
+fn statement_attrs(attrs: &[Attribute]) -> TokenStream {
+    // Doc comments document the invocation, they would only trigger
+    // `unused_doc_comments` on the generated statements
+    let attrs = attrs.iter().filter(|attr| !attr.path().is_ident("doc"));
+    quote! { #(#attrs)* }
+}
+
+fn expand_section(context: &Context, section: &Section, methods: &mut Methods) {
+    let Context {
+        private,
+        app,
+        receiver,
+        set,
+        ..
+    } = context;
+
+    let attrs = statement_attrs(&section.attrs);
+
+    let (target, statement) = match &section.kind {
+        SectionKind::Plugins(plugins) => (
+            &mut methods.build,
+            quote! { #app.add_plugins((#(#plugins),*)); },
+        ),
+        SectionKind::Reflect(entries) => {
+            let entries = entries.iter().map(|entry| expand_reflect(context, entry));
+            (&mut methods.build, quote! { { #(#entries)* } })
+        }
+        SectionKind::Systems {
+            schedule,
+            configs,
+            systems,
+        } => {
+            let systems = apply_system_configs(context, profile_systems(context, systems), configs);
+            let statement = methods.add_systems(context, section, quote! { #schedule }, systems);
+            (&mut methods.build, statement)
+        }
+        SectionKind::Fixed {
+            hz,
+            configs,
+            systems,
+        } => {
+            let systems = apply_system_configs(context, profile_systems(context, systems), configs);
+            let statement = methods.add_systems(
+                context,
+                section,
+                quote! { #private::bevy_app::FixedUpdate },
+                systems,
+            );
+            // Spanned on the rate to report the ones which are not numbers on the invocation
+            let hz = quote_spanned! {hz.span()=> ::core::convert::Into::<f64>::into(#hz) };
+            (
+                &mut methods.build,
+                quote! {
+                    {
+                        #private::set_fixed_timestep(#app, ::std::any::type_name::<Self>(), #hz);
+                        #statement
+                    }
+                },
+            )
+        }
+        SectionKind::Requires(plugins) => (
+            &mut methods.checks,
+            quote! {
+                #(
+                    if !#app.is_plugin_added::<#plugins>() {
+                        ::core::panic!(
+                            "`{}` requires `{}`, add it to the app",
+                            #private::bevy_app::Plugin::name(#receiver),
+                            ::core::any::type_name::<#plugins>(),
+                        );
+                    }
+                )*
+            },
+        ),
+        SectionKind::Conflicts(plugins) => (
+            &mut methods.checks,
+            quote! {
+                #(
+                    if #app.is_plugin_added::<#plugins>() {
+                        ::core::panic!(
+                            "`{}` conflicts with `{}`, only one of them can be added to the app",
+                            #private::bevy_app::Plugin::name(#receiver),
+                            ::core::any::type_name::<#plugins>(),
+                        );
+                    }
+                )*
+            },
+        ),
+        SectionKind::Name(name) => (
+            &mut methods.overrides,
+            quote! {
+                fn name(&#receiver) -> &str {
+                    #name
+                }
+            },
+        ),
+        SectionKind::IsUnique(is_unique) => (
+            &mut methods.overrides,
+            quote! {
+                fn is_unique(&#receiver) -> bool {
+                    #is_unique
+                }
+            },
+        ),
+        SectionKind::Toggle(enabled) => {
+            methods.toggles.push(attrs.clone());
+            methods.finish.push(quote! {
+                #attrs
+                #private::log_plugin_enabled::<Self>(
+                    #app,
+                    #private::bevy_app::Plugin::name(#receiver),
+                );
+            });
+            (
+                &mut methods.build,
+                quote! { #private::init_plugin_enabled::<Self>(#app, #enabled); },
+            )
+        }
+        SectionKind::InitResources(resources) => (
+            &mut methods.build,
+            quote! { { #(#app.init_resource::<#resources>();)* } },
+        ),
+        SectionKind::InitNonSendResources(resources) => (
+            &mut methods.build,
+            quote! { { #(#app.init_non_send_resource::<#resources>();)* } },
+        ),
+        SectionKind::InitSchedules(schedules) => (
+            &mut methods.build,
+            quote! { { #(#app.init_schedule(#schedules);)* } },
+        ),
+        SectionKind::OnEvent {
+            event,
+            schedule,
+            configs,
+            if_present,
+            handlers,
+        } => {
+            // Spanned on the handlers to report the ones with an invalid signature
+            let handlers = handlers.iter().map(|handler| {
+                profile_system(
+                    context,
+                    quote_spanned! {handler.span()=>
+                        #private::handle_events::<#event, _, _>(#handler)
+                    },
+                )
+            });
+            let mut systems = quote! { (#(#handlers),*) };
+            if *if_present {
+                systems = quote! {
+                    #private::bevy_ecs::schedule::IntoSystemConfigs::run_if(
+                        #systems,
+                        #private::bevy_ecs::schedule::common_conditions::on_event::<#event>(),
+                    )
+                };
+            }
+            let systems = apply_system_configs(context, systems, configs);
+            let statement = methods.add_systems(context, section, quote! { #schedule }, systems);
+            (
+                &mut methods.build,
+                quote! {
+                    {
+                        #app.add_event::<#event>();
+                        #statement
+                    }
+                },
+            )
+        }
+        SectionKind::OnEnter { state, systems } => {
+            let statement = methods.add_systems(
+                context,
+                section,
+                quote! { #private::bevy_ecs::schedule::OnEnter(#state) },
+                profile_systems(context, systems),
+            );
+            (&mut methods.build, statement)
+        }
+        SectionKind::OnExit { state, systems } => {
+            let statement = methods.add_systems(
+                context,
+                section,
+                quote! { #private::bevy_ecs::schedule::OnExit(#state) },
+                profile_systems(context, systems),
+            );
+            (&mut methods.build, statement)
+        }
+        SectionKind::OnTransition { from, to, systems } => {
+            let statement = methods.add_systems(
+                context,
+                section,
+                quote! { #private::bevy_ecs::schedule::OnTransition { from: #from, to: #to } },
+                profile_systems(context, systems),
+            );
+            (&mut methods.build, statement)
+        }
+        SectionKind::States(states) => {
+            let states = states.iter().map(|StateEntry { ty, initial }| {
+                let initial = initial.as_ref().map(|initial| {
+                    quote! {
+                        #app.insert_resource(
+                            #private::bevy_ecs::schedule::State::<#ty>::new(#initial),
+                        );
+                    }
+                });
+                quote! {
+                    #initial
+                    #app.add_state::<#ty>();
+                }
+            });
+            (&mut methods.build, quote! { { #(#states)* } })
+        }
+        SectionKind::StateScoped(states) => {
+            let statements = states
+                .iter()
+                .map(|state| {
+                    methods.add_systems(
+                        context,
+                        section,
+                        quote! { #private::bevy_ecs::schedule::OnExit(#state) },
+                        profile_system(context, quote! { #private::despawn_state_scoped(#state) }),
+                    )
+                })
+                .collect::<Vec<TokenStream>>();
+            (&mut methods.build, quote! { { #(#statements)* } })
+        }
+        SectionKind::StateResources { state, resources } => {
+            let plugin = quote! { ::core::any::TypeId::of::<Self>() };
+            let init_systems = resources.iter().map(|resource| {
+                profile_system(
+                    context,
+                    quote! { #private::init_state_resource::<#resource> },
+                )
+            });
+            let remove_systems = resources.iter().map(|resource| {
+                profile_system(
+                    context,
+                    quote! { #private::remove_state_resource::<#resource> },
+                )
+            });
+            let init = methods.add_systems(
+                context,
+                section,
+                quote! { #private::bevy_ecs::schedule::OnEnter(#state) },
+                quote! {
+                    #private::bevy_ecs::schedule::IntoSystemConfigs::in_set(
+                        (#(#init_systems,)*),
+                        #private::StateResourcesSet::Init(#plugin),
+                    )
+                },
+            );
+            let remove = methods.add_systems(
+                context,
+                section,
+                quote! { #private::bevy_ecs::schedule::OnExit(#state) },
+                quote! {
+                    #private::bevy_ecs::schedule::IntoSystemConfigs::in_set(
+                        (#(#remove_systems,)*),
+                        #private::StateResourcesSet::Remove(#plugin),
+                    )
+                },
+            );
+            (&mut methods.build, quote! { { #init #remove } })
+        }
+        SectionKind::Spawn { schedule, entities } => {
+            let schedule = match schedule {
+                SpawnSchedule::Schedule(schedule) => quote! { #schedule },
+                SpawnSchedule::OnEnter(state) => {
+                    quote! { #private::bevy_ecs::schedule::OnEnter(#state) }
+                }
+            };
+            let statement = methods.add_systems(
+                context,
+                section,
+                schedule,
+                profile_system(context, expand_spawn(context, entities)),
+            );
+            (&mut methods.build, statement)
+        }
+        SectionKind::Events(events) => (
+            &mut methods.build,
+            quote! { { #(#app.add_event::<#events>();)* } },
+        ),
+        SectionKind::Resources(resources) => (
+            &mut methods.build,
+            quote! { { #(#app.insert_resource(#resources);)* } },
+        ),
+        SectionKind::Settings(entries) => {
+            let types = entries.iter().map(|entry| &entry.ty).collect::<Vec<_>>();
+            let paths = entries.iter().map(|entry| &entry.path);
+            let statement = methods.add_systems(
+                context,
+                section,
+                quote! { #private::bevy_app::PreUpdate },
+                profile_systems(
+                    context,
+                    &types
+                        .iter()
+                        .map(|ty| syn::parse_quote! { #private::apply_settings::<#ty> })
+                        .collect::<Vec<Expr>>(),
+                ),
+            );
+            let name = &section.name;
+            methods.finish.push(quote_spanned! {name.span()=>
+                #attrs
+                #private::settings_section! {
+                    @finish #(#private::load_settings::<#types>(#app, #paths);)*
+                }
+            });
+            (
+                &mut methods.build,
+                quote_spanned! {name.span()=>
+                    #private::settings_section! {
+                        #(#app.init_resource::<#types>();)*
+                        #statement
+                    }
+                },
+            )
+        }
+        SectionKind::NonSendResources(resources) => (
+            &mut methods.build,
+            quote! { { #(#app.insert_non_send_resource(#resources);)* } },
+        ),
+        SectionKind::Runner(runner) => (&mut methods.build, quote! { #app.set_runner(#runner); }),
+        SectionKind::Assets(assets) => (
+            &mut methods.build,
+            quote! { { #(#private::bevy_asset::AssetApp::init_asset::<#assets>(#app);)* } },
+        ),
+        SectionKind::AssetLoaders(asset_loaders) => (
+            &mut methods.build,
+            quote! {
+                {
+                    #(#private::bevy_asset::AssetApp::init_asset_loader::<#asset_loaders>(#app);)*
+                }
+            },
+        ),
+        SectionKind::Schedules(schedules) => (
+            &mut methods.build,
+            quote! { { #(#app.add_schedule(#schedules);)* } },
+        ),
+        SectionKind::Build(func) => (&mut methods.build, quote! { (#func)(#app); }),
+        SectionKind::Finish(func) => (&mut methods.finish, quote! { (#func)(#app); }),
+        SectionKind::Cleanup(func) => (&mut methods.cleanup, quote! { (#func)(#app); }),
+        // Expanded by `expand_self_test` and `expand_dynamic`, outside of the plugin
+        SectionKind::SelfTest { .. } | SectionKind::Dynamic => return,
+        SectionKind::Profile => (
+            &mut methods.build,
+            quote_spanned! {section.name.span()=>
+                #private::profile_section! {
+                    #private::profile_plugin(#app, ::core::any::type_name::<Self>());
+                }
+            },
+        ),
+        SectionKind::Before(plugins) | SectionKind::After(plugins) => {
+            let method = Ident::new(&section.name.to_string(), Span::call_site());
+            for plugin in plugins {
+                methods
+                    .orderings
+                    .push((attrs.clone(), method.clone(), plugin.clone()));
+            }
+            return;
+        }
+        SectionKind::ConfigureSets { schedule, sets } => {
+            methods
+                .schedules
+                .push((attrs.clone(), quote! { #schedule }));
+            (
+                &mut methods.build,
+                quote! {
+                    #app.configure_sets(
+                        #schedule,
+                        #private::bevy_ecs::schedule::IntoSystemSetConfigs::in_set(
+                            (#(#sets),*),
+                            #set,
+                        ),
+                    );
+                },
+            )
+        }
+        SectionKind::Invariants {
+            filter,
+            severity,
+            invariants,
+        } => {
+            let krate = &context.krate;
+            let plugin = Ident::new("plugin", Span::mixed_site());
+            let severity_ident = Ident::new("severity", Span::mixed_site());
+            let severity = match severity {
+                Some(severity) => quote! { #severity },
+                None => quote! { ::core::default::Default::default() },
+            };
+
+            let system = profile_system(context, expand_invariants(context, filter, invariants));
+            let statement = methods.add_systems(
+                context,
+                section,
+                quote! { #private::bevy_app::Update },
+                system,
+            );
+            (
+                &mut methods.build,
+                quote! {
+                    {
+                        #app.add_event::<#krate::InvariantViolated>();
+                        let #plugin = ::core::any::type_name::<Self>();
+                        let #severity_ident: #krate::InvariantSeverity = #severity;
+                        #statement
+                    }
+                },
+            )
+        }
+    };
+
+    target.push(quote! { #attrs #statement });
+}
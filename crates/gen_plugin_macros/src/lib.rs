@@ -0,0 +1,29 @@
+use proc_macro::TokenStream;
+use syn::parse_macro_input;
+
+mod expand;
+mod parse;
+
+/// Implementation of `bevy_denshi_ika_gen_plugin::gen_plugin`, see its documentation
+#[proc_macro]
+pub fn gen_plugin(input: TokenStream) -> TokenStream {
+    let invocation = parse_macro_input!(input as parse::Invocation);
+
+    expand::expand(invocation).into()
+}
+
+/// Implementation of `bevy_denshi_ika_gen_plugin::gen_plugin_group`, see its documentation
+#[proc_macro]
+pub fn gen_plugin_group(input: TokenStream) -> TokenStream {
+    let invocation = parse_macro_input!(input as parse::GroupInvocation);
+
+    expand::expand_group(invocation).into()
+}
+
+/// Implementation of `bevy_denshi_ika_gen_plugin::GenPlugin`, see its documentation
+#[proc_macro_derive(GenPlugin, attributes(gen_plugin))]
+pub fn derive_gen_plugin(input: TokenStream) -> TokenStream {
+    let derive = parse_macro_input!(input as parse::DerivePlugin);
+
+    expand::expand_derive(derive).into()
+}
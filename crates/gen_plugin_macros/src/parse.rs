@@ -0,0 +1,1518 @@
+use proc_macro2::{Delimiter, Span, TokenTree};
+use quote::ToTokens;
+use syn::{
+    braced, bracketed,
+    ext::IdentExt,
+    parenthesized,
+    parse::{Parse, ParseBuffer, ParseStream},
+    punctuated::Punctuated,
+    spanned::Spanned,
+    token, AttrStyle, Attribute, DeriveInput, Expr, ExprLit, Generics, Ident, Lit, LitStr,
+    MacroDelimiter, Meta, MetaList, Token, Type, Visibility,
+};
+
+pub const SECTIONS: &[&str] = &[
+    "plugins",
+    "reflect",
+    "systems",
+    "fixed",
+    "on_event",
+    "on_enter",
+    "on_exit",
+    "on_transition",
+    "before",
+    "after",
+    "configure_sets",
+    "requires",
+    "conflicts",
+    "name",
+    "is_unique",
+    "toggle",
+    "init_resources",
+    "init_non_send_resources",
+    "init_schedules",
+    "states",
+    "state_scoped",
+    "state_resources",
+    "spawn",
+    "events",
+    "resources",
+    "settings",
+    "non_send_resources",
+    "runner",
+    "assets",
+    "asset_loaders",
+    "schedules",
+    "build",
+    "finish",
+    "cleanup",
+    "invariants",
+    "test_has",
+    "self_test",
+    "profile",
+    "dynamic",
+    "when",
+];
+
+/// `gen_plugin!` forwards its `$crate` before the invocation tokens
+/// so the expansion can refer to the re-exports of the crate
+pub struct Invocation {
+    pub krate: Ident,
+    pub plugin: GenPlugin,
+}
+
+/// `#[derive(GenPlugin)]` on a struct, the sections are read from its
+/// `#[gen_plugin(...)]` attributes
+pub struct DerivePlugin {
+    /// Path of `bevy_denshi_ika_gen_plugin` given by `#[gen_plugin(crate = path)]`
+    pub krate: Option<syn::Path>,
+    pub plugin: GenPlugin,
+}
+
+pub const GROUP_SECTIONS: &[&str] = &["plugins", "before", "after", "set", "disable"];
+
+/// `gen_plugin_group!` forwards its `$crate` like `gen_plugin!`
+pub struct GroupInvocation {
+    pub krate: Ident,
+    pub group: GenPluginGroup,
+}
+
+/// Keys of the system configurations, e.g. `run_if = condition`
+pub const SYSTEM_CONFIGS: &[&str] = &[
+    "run_if",
+    "distributive_run_if",
+    "in_state",
+    "in_set",
+    "before",
+    "after",
+    "ambiguous_with",
+];
+
+/// System configurations taking no value, e.g. `chain`
+pub const SYSTEM_CONFIG_FLAGS: &[&str] = &["chain", "ambiguous_with_all"];
+
+pub const WHEN_CONDITIONS: &[&str] = &["cfg", "run_if"];
+
+pub const MAIN_OPTIONS: &[&str] = &["headless", "frames", "args", "app"];
+
+pub const INVARIANTS: &[&str] = &["has", "not_has", "check", "parent", "children"];
+
+pub struct GenPlugin {
+    pub header: Header,
+    pub sections: Vec<Section>,
+}
+
+pub enum Header {
+    Main(Box<MainOptions>),
+    Plugin(PluginHeader),
+}
+
+/// Options of `main(...)`
+#[derive(Default)]
+pub struct MainOptions {
+    /// Replaces the runner with `ScheduleRunnerPlugin`
+    pub headless: bool,
+    /// Number of frames after which the app exits
+    pub frames: Option<Expr>,
+    /// Function parsing the command line arguments into a resource
+    pub args: Option<Expr>,
+    /// Function creating the `App`
+    pub app: Option<Expr>,
+}
+
+pub struct PluginHeader {
+    pub vis: Visibility,
+    pub name: Ident,
+    pub generics: Generics,
+    pub fields: Vec<PluginField>,
+    /// The struct is defined outside of the invocation (`impl for` header or derive)
+    pub existing: bool,
+}
+
+pub struct PluginField {
+    pub attrs: Vec<Attribute>,
+    pub vis: Visibility,
+    pub name: Ident,
+    pub ty: Type,
+    pub default: Option<Expr>,
+}
+
+/// A method of [`IntoSystemConfigs`] applied to the systems of a section,
+/// `in_state` is applied as a `run_if` condition
+pub struct SystemConfig {
+    pub name: Ident,
+    pub value: Option<Expr>,
+}
+
+pub struct GenPluginGroup {
+    pub header: PluginHeader,
+    pub sections: Vec<GroupSection>,
+}
+
+pub struct GroupSection {
+    pub attrs: Vec<Attribute>,
+    pub kind: GroupSectionKind,
+}
+
+pub enum GroupSectionKind {
+    Plugins(Vec<Expr>),
+    Before { target: Type, plugins: Vec<Expr> },
+    After { target: Type, plugins: Vec<Expr> },
+    Set(Vec<Expr>),
+    Disable(Vec<Type>),
+}
+
+/// A predicate of the `invariants` section checked on each entity
+pub enum Invariant {
+    Has(Vec<Type>),
+    NotHas(Vec<Type>),
+    /// Checked only when the entity has the component
+    Check {
+        component: Box<Type>,
+        predicate: Box<Expr>,
+    },
+    Parent(Vec<Type>),
+    Children(Vec<Type>),
+}
+
+/// A type of the `reflect` section, with the traits of the type data to register
+/// (e.g. `Component` for `ReflectComponent`)
+pub struct ReflectEntry {
+    pub ty: Type,
+    pub data: Vec<Ident>,
+}
+
+/// A resource of the `settings` section with the path of its file
+pub struct SettingsEntry {
+    pub ty: Type,
+    pub path: Expr,
+}
+
+pub struct StateEntry {
+    pub ty: Type,
+    pub initial: Option<Expr>,
+}
+
+/// Schedule of the `spawn` section
+pub enum SpawnSchedule {
+    Schedule(Expr),
+    OnEnter(Expr),
+}
+
+/// An entity of the `spawn` section: its `Name`, components and children
+pub struct SpawnEntity {
+    pub name: Option<LitStr>,
+    pub components: Vec<Expr>,
+    pub children: Vec<SpawnEntity>,
+}
+
+pub struct Section {
+    /// Attributes of the section and of its enclosing `when` blocks
+    pub attrs: Vec<Attribute>,
+    pub name: Ident,
+    pub kind: SectionKind,
+    /// Run conditions of the enclosing `when` blocks, applied to the systems of the section
+    pub run_if: Vec<Expr>,
+}
+
+/// Conditions of the `when` blocks enclosing a section
+#[derive(Clone, Default)]
+struct WhenScope {
+    attrs: Vec<Attribute>,
+    run_if: Vec<Expr>,
+}
+
+pub enum SectionKind {
+    Plugins(Vec<Expr>),
+    Reflect(Vec<ReflectEntry>),
+    Systems {
+        schedule: Expr,
+        configs: Vec<SystemConfig>,
+        systems: Vec<Expr>,
+    },
+    Fixed {
+        hz: Expr,
+        configs: Vec<SystemConfig>,
+        systems: Vec<Expr>,
+    },
+    InitResources(Vec<Type>),
+    InitNonSendResources(Vec<Type>),
+    InitSchedules(Vec<Expr>),
+    OnEvent {
+        event: Type,
+        schedule: Expr,
+        configs: Vec<SystemConfig>,
+        /// Runs the handlers only when there are events
+        if_present: bool,
+        handlers: Vec<Expr>,
+    },
+    OnEnter {
+        state: Expr,
+        systems: Vec<Expr>,
+    },
+    OnExit {
+        state: Expr,
+        systems: Vec<Expr>,
+    },
+    OnTransition {
+        from: Expr,
+        to: Expr,
+        systems: Vec<Expr>,
+    },
+    Before(Vec<Type>),
+    After(Vec<Type>),
+    ConfigureSets {
+        schedule: Expr,
+        sets: Vec<Expr>,
+    },
+    Requires(Vec<Type>),
+    Conflicts(Vec<Type>),
+    Name(Expr),
+    IsUnique(Expr),
+    /// Initial state of the `PluginEnabled` resource gating the systems of the plugin
+    Toggle(Expr),
+    States(Vec<StateEntry>),
+    StateScoped(Vec<Expr>),
+    StateResources {
+        state: Expr,
+        resources: Vec<Type>,
+    },
+    Spawn {
+        schedule: SpawnSchedule,
+        entities: Vec<SpawnEntity>,
+    },
+    Events(Vec<Type>),
+    Resources(Vec<Expr>),
+    Settings(Vec<SettingsEntry>),
+    NonSendResources(Vec<Expr>),
+    Runner(Expr),
+    Assets(Vec<Type>),
+    AssetLoaders(Vec<Type>),
+    Schedules(Vec<Expr>),
+    Build(Expr),
+    Finish(Expr),
+    Cleanup(Expr),
+    Invariants {
+        filter: Type,
+        severity: Option<Expr>,
+        invariants: Vec<Invariant>,
+    },
+    SelfTest {
+        frames: Expr,
+        /// Function preparing the app before the plugins are added
+        setup: Option<Expr>,
+        plugins: Vec<Expr>,
+    },
+    Profile,
+    Dynamic,
+}
+
+impl Parse for Invocation {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let krate = input.call(Ident::parse_any)?;
+        input.parse::<Token![;]>()?;
+
+        Ok(Self {
+            krate,
+            plugin: input.parse()?,
+        })
+    }
+}
+
+impl Parse for GenPlugin {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let header = input.parse()?;
+        input.parse::<Token![;]>()?;
+
+        let mut sections = Vec::new();
+        let mut errors = Errors::default();
+        parse_sections(input, &WhenScope::default(), &mut sections, &mut errors)?;
+        check_single_sections(&sections, &mut errors);
+
+        errors.finish()?;
+
+        Ok(Self { header, sections })
+    }
+}
+
+impl Parse for DerivePlugin {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let input = input.parse::<DeriveInput>()?;
+
+        let mut krate = None;
+        let mut sections = Vec::new();
+        let mut errors = Errors::default();
+        for attr in &input.attrs {
+            if !attr.path().is_ident("gen_plugin") {
+                continue;
+            }
+            let result = attr.parse_args_with(|content: ParseStream| {
+                if content.peek(Token![crate]) && content.peek2(Token![=]) {
+                    let key = content.parse::<Token![crate]>()?;
+                    content.parse::<Token![=]>()?;
+                    let path = content.parse::<syn::Path>()?;
+                    content.parse::<Option<Token![;]>>()?;
+                    if krate.replace(path).is_some() {
+                        errors.push(syn::Error::new(key.span, "duplicate `crate` path"));
+                    }
+                }
+                parse_sections(content, &WhenScope::default(), &mut sections, &mut errors)
+            });
+            if let Err(error) = result {
+                errors.push(error);
+            }
+        }
+        check_single_sections(&sections, &mut errors);
+
+        errors.finish()?;
+
+        Ok(Self {
+            krate,
+            plugin: GenPlugin {
+                header: Header::Plugin(PluginHeader {
+                    vis: input.vis,
+                    name: input.ident,
+                    generics: input.generics,
+                    fields: Vec::new(),
+                    existing: true,
+                }),
+                sections,
+            },
+        })
+    }
+}
+
+impl Parse for GroupInvocation {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let krate = input.call(Ident::parse_any)?;
+        input.parse::<Token![;]>()?;
+
+        Ok(Self {
+            krate,
+            group: input.parse()?,
+        })
+    }
+}
+
+impl Parse for GenPluginGroup {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let span = input.span();
+        let header = match input.parse()? {
+            Header::Main(_) => {
+                return Err(syn::Error::new(
+                    span,
+                    "a plugin group cannot be `main`, use `gen_plugin!`",
+                ))
+            }
+            Header::Plugin(header) => header,
+        };
+        input.parse::<Token![;]>()?;
+
+        let mut sections = Vec::new();
+        let mut errors = Errors::default();
+
+        while !input.is_empty() {
+            match input.parse() {
+                Ok(section) => sections.push(section),
+                Err(error) => {
+                    errors.push(error);
+                    skip_section(input)?;
+                }
+            }
+        }
+
+        errors.finish()?;
+
+        Ok(Self { header, sections })
+    }
+}
+
+impl Parse for Header {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(Ident)
+            && (input.peek2(Token![;]) || input.peek2(token::Paren))
+            && input.fork().parse::<Ident>()? == "main"
+        {
+            input.parse::<Ident>()?;
+            let mut options = MainOptions::default();
+            if input.peek(token::Paren) {
+                let content;
+                parenthesized!(content in input);
+                options = content.parse()?;
+            }
+            return Ok(Self::Main(Box::new(options)));
+        }
+
+        // `impl for` implements the plugin for a struct defined outside of the invocation
+        if input.peek(Token![impl]) {
+            input.parse::<Token![impl]>()?;
+            input.parse::<Token![for]>()?;
+            let name = input.parse::<Ident>()?;
+            let mut generics = input.parse::<Generics>()?;
+            generics.where_clause = input.parse()?;
+            if input.peek(token::Brace) {
+                return Err(
+                    input.error("the fields of an `impl for` plugin are declared on its struct")
+                );
+            }
+
+            return Ok(Self::Plugin(PluginHeader {
+                // Visibility of the generated system set, the plugin may be public
+                vis: Visibility::Public(Token![pub](name.span())),
+                name,
+                generics,
+                fields: Vec::new(),
+                existing: true,
+            }));
+        }
+
+        let vis = input.parse()?;
+        let name = input.parse()?;
+        let mut generics = input.parse::<Generics>()?;
+        generics.where_clause = input.parse()?;
+
+        let mut fields = Vec::new();
+        if input.peek(token::Brace) {
+            let content;
+            braced!(content in input);
+            fields = Punctuated::<PluginField, Token![,]>::parse_terminated(&content)?
+                .into_iter()
+                .collect();
+        }
+
+        Ok(Self::Plugin(PluginHeader {
+            vis,
+            name,
+            generics,
+            fields,
+            existing: false,
+        }))
+    }
+}
+
+impl Parse for MainOptions {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut options = Self::default();
+        let mut seen = Vec::new();
+
+        while !input.is_empty() {
+            let name = input.parse::<Ident>()?;
+            let name_string = name.to_string();
+            if !MAIN_OPTIONS.contains(&name_string.as_str()) {
+                return Err(unknown(&name, "`main` option", MAIN_OPTIONS));
+            }
+            if seen.contains(&name_string) {
+                return Err(syn::Error::new(
+                    name.span(),
+                    format!("duplicated `{name_string}` option"),
+                ));
+            }
+
+            let value = match input.parse::<Option<Token![=]>>()? {
+                Some(_) if name == "headless" => {
+                    return Err(syn::Error::new(
+                        name.span(),
+                        "`headless` does not take a value",
+                    ))
+                }
+                Some(_) => Some(input.parse::<Expr>()?),
+                None if name == "headless" => None,
+                None => {
+                    return Err(syn::Error::new(
+                        name.span(),
+                        format!("`{name_string}` expects a value, e.g. `{name_string} = ...`"),
+                    ))
+                }
+            };
+            match name_string.as_str() {
+                "headless" => options.headless = true,
+                "frames" => options.frames = value,
+                "args" => options.args = value,
+                _ => options.app = value,
+            }
+            seen.push(name_string);
+
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+impl Parse for PluginField {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        let vis = input.parse()?;
+        let name = input.parse()?;
+        input.parse::<Token![:]>()?;
+        let ty = input.parse()?;
+        let default = match input.parse::<Option<Token![=]>>()? {
+            Some(_) => Some(input.parse()?),
+            None => None,
+        };
+
+        Ok(Self {
+            attrs,
+            vis,
+            name,
+            ty,
+            default,
+        })
+    }
+}
+
+impl SystemConfig {
+    /// Parses `key = value` or a flag, a lone expression is accepted as
+    /// the state of `in_state` when `allow_state` is set
+    fn parse(input: ParseStream, allow_state: bool) -> syn::Result<Self> {
+        if input.peek(Ident) && input.peek2(Token![=]) {
+            let name = input.parse::<Ident>()?;
+            let name_string = name.to_string();
+            if SYSTEM_CONFIG_FLAGS.contains(&name_string.as_str()) {
+                return Err(syn::Error::new(
+                    name.span(),
+                    format!("`{name_string}` does not take a value"),
+                ));
+            }
+            if !SYSTEM_CONFIGS.contains(&name_string.as_str()) {
+                return Err(unknown(&name, "system configuration", SYSTEM_CONFIGS));
+            }
+            input.parse::<Token![=]>()?;
+
+            return Ok(Self {
+                name,
+                value: Some(input.parse()?),
+            });
+        }
+
+        let fork = input.fork();
+        if let Ok(name) = fork.parse::<Ident>() {
+            if (fork.is_empty() || fork.peek(Token![,]))
+                && SYSTEM_CONFIG_FLAGS.contains(&name.to_string().as_str())
+            {
+                input.parse::<Ident>()?;
+                return Ok(Self { name, value: None });
+            }
+        }
+
+        if !allow_state {
+            return Err(input
+                .error("expected a system configuration, e.g. `run_if = condition` or `chain`"));
+        }
+
+        let state = input.parse::<Expr>()?;
+        Ok(Self {
+            name: Ident::new("in_state", state.span()),
+            value: Some(state),
+        })
+    }
+}
+
+impl Parse for Invariant {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let name = input.parse::<Ident>()?;
+        let name_string = name.to_string();
+        if !INVARIANTS.contains(&name_string.as_str()) {
+            return Err(unknown(&name, "invariant", INVARIANTS));
+        }
+
+        let content;
+        let parens = parenthesized!(content in input);
+
+        let invariant = match name_string.as_str() {
+            "has" => Self::Has(parse_list(&content)?),
+            "not_has" => Self::NotHas(parse_list(&content)?),
+            "check" => {
+                let component = content.parse()?;
+                content.parse::<Token![,]>()?;
+                let predicate = content.parse()?;
+                content.parse::<Option<Token![,]>>()?;
+                if !content.is_empty() {
+                    return Err(content.error("unexpected tokens after the predicate"));
+                }
+
+                return Ok(Self::Check {
+                    component,
+                    predicate,
+                });
+            }
+            "parent" => Self::Parent(parse_list(&content)?),
+            "children" => Self::Children(parse_list(&content)?),
+            _ => unreachable!("invariant names are checked against `INVARIANTS`"),
+        };
+
+        if let Self::Has(components)
+        | Self::NotHas(components)
+        | Self::Parent(components)
+        | Self::Children(components) = &invariant
+        {
+            if components.is_empty() {
+                return Err(syn::Error::new(
+                    parens.span.join(),
+                    "expected at least one component",
+                ));
+            }
+        }
+
+        Ok(invariant)
+    }
+}
+
+impl Parse for ReflectEntry {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let ty = input.parse()?;
+        let mut data = Vec::new();
+        if input.parse::<Option<Token![:]>>()?.is_some() {
+            data = Punctuated::<Ident, Token![+]>::parse_separated_nonempty(input)?
+                .into_iter()
+                .collect();
+        }
+
+        Ok(Self { ty, data })
+    }
+}
+
+impl Parse for SettingsEntry {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let ty = input.parse()?;
+        input.parse::<Token![=]>().map_err(|error| {
+            syn::Error::new(
+                error.span(),
+                "expected the path of the settings file, e.g. `MySettings = \"settings/my.settings.ron\"`",
+            )
+        })?;
+        let path = input.parse()?;
+
+        // Other paths are checked when the plugin is built
+        if let Expr::Lit(ExprLit {
+            lit: Lit::Str(literal),
+            ..
+        }) = &path
+        {
+            if !literal.value().ends_with(".settings.ron") {
+                return Err(syn::Error::new(
+                    literal.span(),
+                    "settings files are loaded by their `.settings.ron` extension, \
+                    e.g. `\"settings/my.settings.ron\"`",
+                ));
+            }
+        }
+
+        Ok(Self { ty, path })
+    }
+}
+
+impl Parse for SpawnSchedule {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let fork = input.fork();
+        if fork.parse::<Ident>().is_ok_and(|key| key == "on_enter") && fork.peek(Token![=]) {
+            let state = parse_key(
+                input,
+                "on_enter",
+                "a state, e.g. `on_enter = MyState::Game`",
+            )?;
+            parse_end(input)?;
+            return Ok(Self::OnEnter(state));
+        }
+
+        Ok(Self::Schedule(parse_single(input)?))
+    }
+}
+
+impl Parse for SpawnEntity {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let name = match input.peek(LitStr) {
+            true => Some(input.parse()?),
+            false => None,
+        };
+
+        let components = match input.peek(token::Paren) {
+            true => {
+                let content;
+                parenthesized!(content in input);
+                parse_list(&content)?
+            }
+            false if name.is_some() => Vec::new(),
+            false => {
+                return Err(
+                    input.error("expected an entity, e.g. `\"Player\" (Transform::default())`")
+                )
+            }
+        };
+
+        let fork = input.fork();
+        let children = match fork.parse::<Ident>() {
+            Ok(ident) if ident == "children" => {
+                input.parse::<Ident>()?;
+                if !input.peek(token::Bracket) {
+                    return Err(
+                        input.error("expected the children in brackets, e.g. `children[...]`")
+                    );
+                }
+                let content;
+                bracketed!(content in input);
+                parse_list(&content)?
+            }
+            _ => Vec::new(),
+        };
+
+        Ok(Self {
+            name,
+            components,
+            children,
+        })
+    }
+}
+
+impl Parse for StateEntry {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let ty = input.parse()?;
+        let initial = match input.parse::<Option<Token![=]>>()? {
+            Some(_) => Some(input.parse()?),
+            None => None,
+        };
+
+        Ok(Self { ty, initial })
+    }
+}
+
+impl Parse for Section {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        let name = input.parse::<Ident>().map_err(|error| {
+            syn::Error::new(error.span(), "expected a section name, e.g. `systems`")
+        })?;
+
+        let name_string = name.to_string();
+        if !SECTIONS.contains(&name_string.as_str()) {
+            return Err(unknown_section(&name));
+        }
+
+        // Sections without arguments
+        let flag = match name_string.as_str() {
+            "profile" => Some(SectionKind::Profile),
+            "dynamic" => Some(SectionKind::Dynamic),
+            _ => None,
+        };
+        if let Some(kind) = flag {
+            if input.peek(token::Paren) {
+                return Err(input.error(format!(
+                    "the `{name_string}` section takes no arguments, e.g. `{name_string};`"
+                )));
+            }
+            parse_section_end(input, &name)?;
+
+            return Ok(Self {
+                attrs,
+                name,
+                kind,
+                run_if: Vec::new(),
+            });
+        }
+
+        let content;
+        parenthesized!(content in input);
+
+        let kind = match name_string.as_str() {
+            "plugins" => SectionKind::Plugins(parse_list(&content)?),
+            "reflect" => SectionKind::Reflect(parse_list(&content)?),
+            "systems" => {
+                let schedule = content.parse()?;
+
+                SectionKind::Systems {
+                    schedule,
+                    configs: parse_system_configs(&content, true)?,
+                    systems: parse_systems(input, &name_string)?,
+                }
+            }
+            "fixed" => {
+                let hz = parse_key(
+                    &content,
+                    "hz",
+                    "the rate of the fixed timestep, e.g. `hz = 60.0`",
+                )?;
+
+                SectionKind::Fixed {
+                    hz,
+                    configs: parse_system_configs(&content, false)?,
+                    systems: parse_systems(input, &name_string)?,
+                }
+            }
+            "on_event" => {
+                let event = content.parse()?;
+                content.parse::<Token![,]>()?;
+                let schedule = content.parse()?;
+
+                let mut if_present = false;
+                let mut configs = Vec::new();
+                while content.parse::<Option<Token![,]>>()?.is_some() && !content.is_empty() {
+                    let fork = content.fork();
+                    if fork.parse::<Ident>().is_ok_and(|flag| flag == "if_present")
+                        && (fork.is_empty() || fork.peek(Token![,]))
+                    {
+                        content.parse::<Ident>()?;
+                        if_present = true;
+                        continue;
+                    }
+                    configs.push(SystemConfig::parse(&content, false)?);
+                }
+                if !content.is_empty() {
+                    return Err(content.error("expected `,`"));
+                }
+
+                SectionKind::OnEvent {
+                    event,
+                    schedule,
+                    configs,
+                    if_present,
+                    handlers: parse_second_group(input, &name_string, "handlers")?,
+                }
+            }
+            "on_enter" => SectionKind::OnEnter {
+                state: parse_single(&content)?,
+                systems: parse_systems(input, &name_string)?,
+            },
+            "on_exit" => SectionKind::OnExit {
+                state: parse_single(&content)?,
+                systems: parse_systems(input, &name_string)?,
+            },
+            "on_transition" => {
+                let from = content.parse()?;
+                content.parse::<Token![,]>()?;
+                let to = content.parse()?;
+                parse_end(&content)?;
+
+                SectionKind::OnTransition {
+                    from,
+                    to,
+                    systems: parse_systems(input, &name_string)?,
+                }
+            }
+            "before" => SectionKind::Before(parse_list(&content)?),
+            "after" => SectionKind::After(parse_list(&content)?),
+            "configure_sets" => SectionKind::ConfigureSets {
+                schedule: parse_single(&content)?,
+                sets: parse_second_group(input, &name_string, "sets")?,
+            },
+            "requires" => SectionKind::Requires(parse_list(&content)?),
+            "conflicts" => SectionKind::Conflicts(parse_list(&content)?),
+            "name" => SectionKind::Name(parse_single(&content)?),
+            "is_unique" => SectionKind::IsUnique(parse_single(&content)?),
+            "toggle" => {
+                let enabled = parse_key(
+                    &content,
+                    "enabled",
+                    "the initial state of the plugin, e.g. `enabled = true`",
+                )?;
+                parse_end(&content)?;
+
+                SectionKind::Toggle(enabled)
+            }
+            "init_resources" => SectionKind::InitResources(parse_list(&content)?),
+            "init_non_send_resources" => SectionKind::InitNonSendResources(parse_list(&content)?),
+            "init_schedules" => SectionKind::InitSchedules(parse_list(&content)?),
+            "states" => SectionKind::States(parse_list(&content)?),
+            "state_scoped" => SectionKind::StateScoped(parse_list(&content)?),
+            "state_resources" => SectionKind::StateResources {
+                state: parse_single(&content)?,
+                resources: parse_second_group(input, &name_string, "resources")?,
+            },
+            "spawn" => SectionKind::Spawn {
+                schedule: content.parse()?,
+                entities: parse_second_group(input, &name_string, "entities")?,
+            },
+            "events" => SectionKind::Events(parse_list(&content)?),
+            "resources" => SectionKind::Resources(parse_list(&content)?),
+            "settings" => SectionKind::Settings(parse_list(&content)?),
+            "non_send_resources" => SectionKind::NonSendResources(parse_list(&content)?),
+            "runner" => SectionKind::Runner(parse_single(&content)?),
+            "assets" => SectionKind::Assets(parse_list(&content)?),
+            "asset_loaders" => SectionKind::AssetLoaders(parse_list(&content)?),
+            "schedules" => SectionKind::Schedules(parse_list(&content)?),
+            "build" => SectionKind::Build(parse_single(&content)?),
+            "finish" => SectionKind::Finish(parse_single(&content)?),
+            "cleanup" => SectionKind::Cleanup(parse_single(&content)?),
+            "invariants" => {
+                let filter = content.parse()?;
+
+                let mut severity = None;
+                if content.parse::<Option<Token![,]>>()?.is_some() && !content.is_empty() {
+                    severity = Some(parse_key(
+                        &content,
+                        "severity",
+                        "the severity of the violations, e.g. `severity = InvariantSeverity::Panic`",
+                    )?);
+                    content.parse::<Option<Token![,]>>()?;
+                }
+                if !content.is_empty() {
+                    return Err(
+                        content.error("unexpected tokens, expected `filter, severity = ...`")
+                    );
+                }
+
+                let span = input.span();
+                let invariants =
+                    parse_second_group::<Invariant>(input, &name_string, "invariants")?;
+                if invariants.is_empty() {
+                    return Err(syn::Error::new(span, "expected at least one invariant"));
+                }
+
+                SectionKind::Invariants {
+                    filter,
+                    severity,
+                    invariants,
+                }
+            }
+            // Kept from before `invariants`, checking that the entities have the components
+            "test_has" => {
+                let filter = content.parse()?;
+                content.parse::<Token![,]>()?;
+
+                let required_content;
+                let parens = parenthesized!(required_content in content);
+                let required = parse_list::<Type>(&required_content)?;
+                if required.is_empty() {
+                    return Err(syn::Error::new(
+                        parens.span.join(),
+                        "expected at least one component",
+                    ));
+                }
+                content.parse::<Option<Token![,]>>()?;
+                if !content.is_empty() {
+                    return Err(content.error("unexpected tokens, expected `filter, (components)`"));
+                }
+
+                SectionKind::Invariants {
+                    filter,
+                    severity: None,
+                    invariants: vec![Invariant::Has(required)],
+                }
+            }
+            "self_test" => {
+                let frames = parse_key(
+                    &content,
+                    "frames",
+                    "the number of updates, e.g. `frames = 10`",
+                )?;
+                let mut setup = None;
+                if content.parse::<Option<Token![,]>>()?.is_some() && !content.is_empty() {
+                    setup = Some(parse_key(
+                        &content,
+                        "setup",
+                        "a function preparing the app, e.g. `setup = register_assets`",
+                    )?);
+                }
+                parse_end(&content)?;
+
+                let plugins = match input.peek(token::Paren) {
+                    true => parse_second_group(input, &name_string, "plugins")?,
+                    false => Vec::new(),
+                };
+
+                SectionKind::SelfTest {
+                    frames,
+                    setup,
+                    plugins,
+                }
+            }
+            _ => unreachable!("section names are checked against `SECTIONS`"),
+        };
+
+        parse_section_end(input, &name)?;
+
+        Ok(Self {
+            attrs,
+            name,
+            kind,
+            run_if: Vec::new(),
+        })
+    }
+}
+
+impl Parse for GroupSection {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        let name = input.parse::<Ident>().map_err(|error| {
+            syn::Error::new(error.span(), "expected a section name, e.g. `plugins`")
+        })?;
+
+        let name_string = name.to_string();
+        if !GROUP_SECTIONS.contains(&name_string.as_str()) {
+            return Err(unknown(&name, "section", GROUP_SECTIONS));
+        }
+
+        let content;
+        parenthesized!(content in input);
+
+        let kind = match name_string.as_str() {
+            "plugins" => GroupSectionKind::Plugins(parse_list(&content)?),
+            "before" => GroupSectionKind::Before {
+                target: parse_single(&content)?,
+                plugins: parse_second_group(input, &name_string, "plugins")?,
+            },
+            "after" => GroupSectionKind::After {
+                target: parse_single(&content)?,
+                plugins: parse_second_group(input, &name_string, "plugins")?,
+            },
+            "set" => GroupSectionKind::Set(parse_list(&content)?),
+            "disable" => GroupSectionKind::Disable(parse_list(&content)?),
+            _ => unreachable!("section names are checked against `GROUP_SECTIONS`"),
+        };
+
+        input.parse::<Token![;]>().map_err(|error| {
+            syn::Error::new(
+                error.span(),
+                format!("expected `;` after the `{name_string}` section"),
+            )
+        })?;
+
+        Ok(Self { attrs, kind })
+    }
+}
+
+/// Parses the sections up to the end of the input, the sections of `when` blocks
+/// are flattened and take the conditions of the blocks
+fn parse_sections(
+    input: ParseStream,
+    scope: &WhenScope,
+    sections: &mut Vec<Section>,
+    errors: &mut Errors,
+) -> syn::Result<()> {
+    while !input.is_empty() {
+        let fork = input.fork();
+        fork.call(Attribute::parse_outer)?;
+        if fork.peek(Ident) && fork.parse::<Ident>()? == "when" {
+            match parse_when(input, scope) {
+                Ok((scope, content)) => parse_sections(&content, &scope, sections, errors)?,
+                Err(error) => {
+                    errors.push(error);
+                    skip_section(input)?;
+                }
+            }
+            continue;
+        }
+
+        match input.parse::<Section>() {
+            Ok(mut section) => {
+                section.attrs.splice(0..0, scope.attrs.iter().cloned());
+                section.run_if = scope.run_if.clone();
+                sections.push(section);
+            }
+            Err(error) => {
+                errors.push(error);
+                skip_section(input)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Parses the conditions of a `when` block, returns the scope of its sections and its content
+fn parse_when<'a>(
+    input: &ParseBuffer<'a>,
+    scope: &WhenScope,
+) -> syn::Result<(WhenScope, ParseBuffer<'a>)> {
+    let mut scope = scope.clone();
+    scope.attrs.extend(input.call(Attribute::parse_outer)?);
+    input.parse::<Ident>()?;
+
+    if !input.peek(token::Paren) {
+        return Err(
+            input.error("expected the conditions of the block, e.g. `when(run_if = condition)`")
+        );
+    }
+    let conditions;
+    let paren = parenthesized!(conditions in input);
+    if conditions.is_empty() {
+        return Err(syn::Error::new(
+            paren.span.join(),
+            "expected a condition, e.g. `cfg(feature = \"dev\")` or `run_if = condition`",
+        ));
+    }
+    while !conditions.is_empty() {
+        let name = conditions.parse::<Ident>()?;
+        match name.to_string().as_str() {
+            "cfg" => {
+                let predicate;
+                let paren = parenthesized!(predicate in conditions);
+                scope.attrs.push(Attribute {
+                    pound_token: Default::default(),
+                    style: AttrStyle::Outer,
+                    bracket_token: Default::default(),
+                    meta: Meta::List(MetaList {
+                        path: name.into(),
+                        delimiter: MacroDelimiter::Paren(paren),
+                        tokens: predicate.parse()?,
+                    }),
+                });
+            }
+            "run_if" => {
+                conditions.parse::<Token![=]>()?;
+                scope.run_if.push(conditions.parse()?);
+            }
+            _ => return Err(unknown(&name, "`when` condition", WHEN_CONDITIONS)),
+        }
+
+        if !conditions.is_empty() {
+            conditions.parse::<Token![,]>()?;
+        }
+    }
+
+    if !input.peek(token::Brace) {
+        return Err(input.error("expected the sections of the block in braces"));
+    }
+    let content;
+    braced!(content in input);
+    input.parse::<Option<Token![;]>>()?;
+
+    Ok((scope, content))
+}
+
+/// Reports the sections overriding a single method which are present more than once
+/// with the same `cfg` attributes, sections under different `cfg` (e.g. a runner per
+/// platform) are left to rustc
+fn check_single_sections(sections: &[Section], errors: &mut Errors) {
+    // Sections overriding a single method, with their `cfg` and the span of their
+    // first occurrence
+    let mut single_spans = Vec::<(String, Vec<String>, Span)>::new();
+
+    for section in sections {
+        if let SectionKind::Runner(_)
+        | SectionKind::Name(_)
+        | SectionKind::IsUnique(_)
+        | SectionKind::Toggle(_)
+        | SectionKind::SelfTest { .. }
+        | SectionKind::Profile
+        | SectionKind::Dynamic = section.kind
+        {
+            let name = section.name.to_string();
+            let cfgs = section_cfgs(section);
+            match single_spans
+                .iter()
+                .find(|(single, single_cfgs, _)| *single == name && *single_cfgs == cfgs)
+            {
+                Some((_, _, first)) => {
+                    let message = match name.as_str() {
+                        "runner" => "duplicate `runner` section, an app can only have one runner"
+                            .to_string(),
+                        _ => format!("duplicate `{name}` section"),
+                    };
+                    errors.push(syn::Error::new(section.name.span(), message));
+                    errors.push(syn::Error::new(
+                        *first,
+                        format!("first `{name}` section here"),
+                    ));
+                }
+                None => single_spans.push((name, cfgs, section.name.span())),
+            }
+        }
+    }
+}
+
+/// Predicates of the `cfg` attributes of the section, sorted so their order does not matter
+fn section_cfgs(section: &Section) -> Vec<String> {
+    let mut cfgs = section
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("cfg"))
+        .map(|attr| attr.meta.to_token_stream().to_string())
+        .collect::<Vec<String>>();
+    cfgs.sort();
+    cfgs.dedup();
+    cfgs
+}
+
+/// Parses the `;` ending the section, reported on the section name when the
+/// invocation ends as the error would otherwise span the whole invocation
+fn parse_section_end(input: ParseStream, section: &Ident) -> syn::Result<()> {
+    input.parse::<Token![;]>().map_err(|error| {
+        let span = match input.is_empty() {
+            true => section.span(),
+            false => error.span(),
+        };
+        syn::Error::new(span, format!("expected `;` after the `{section}` section"))
+    })?;
+
+    Ok(())
+}
+
+/// Parses `key = value`, `expected` describes the value in the error
+fn parse_key(input: ParseStream, key: &str, expected: &str) -> syn::Result<Expr> {
+    let ident = input
+        .call(Ident::parse_any)
+        .map_err(|error| syn::Error::new(error.span(), format!("expected {expected}")))?;
+    if ident != key {
+        return Err(syn::Error::new(
+            ident.span(),
+            format!("expected {expected}"),
+        ));
+    }
+    input.parse::<Token![=]>()?;
+
+    input.parse()
+}
+
+/// Parses the system configurations following the first argument of a section
+fn parse_system_configs(input: ParseStream, allow_state: bool) -> syn::Result<Vec<SystemConfig>> {
+    let mut configs = Vec::new();
+    while input.parse::<Option<Token![,]>>()?.is_some() && !input.is_empty() {
+        configs.push(SystemConfig::parse(
+            input,
+            allow_state && configs.is_empty(),
+        )?);
+    }
+    if !input.is_empty() {
+        return Err(input.error("expected `,`"));
+    }
+
+    Ok(configs)
+}
+
+fn parse_systems(input: ParseStream, section: &str) -> syn::Result<Vec<Expr>> {
+    parse_second_group(input, section, "systems")
+}
+
+/// Parses the second parenthesized group of sections like `systems(Update)(my_system)`
+fn parse_second_group<T: Parse>(
+    input: ParseStream,
+    section: &str,
+    items: &str,
+) -> syn::Result<Vec<T>> {
+    if !input.peek(token::Paren) {
+        return Err(input.error(format!(
+            "expected the {items} in a second parenthesized group, e.g. `{section}(...)(...)`"
+        )));
+    }
+
+    let content;
+    parenthesized!(content in input);
+    parse_list(&content)
+}
+
+fn parse_list<T: Parse>(input: ParseStream) -> syn::Result<Vec<T>> {
+    Ok(Punctuated::<T, Token![,]>::parse_terminated(input)?
+        .into_iter()
+        .collect())
+}
+
+fn parse_single<T: Parse>(input: ParseStream) -> syn::Result<T> {
+    let value = input.parse()?;
+    parse_end(input)?;
+    Ok(value)
+}
+
+fn parse_end(input: ParseStream) -> syn::Result<()> {
+    input.parse::<Option<Token![,]>>()?;
+
+    if !input.is_empty() {
+        return Err(input.error("unexpected tokens, this section takes a single argument"));
+    }
+
+    Ok(())
+}
+
+/// Skips the tokens up to and including the next `;` or `when` block
+/// to resume parsing at the next section
+fn skip_section(input: ParseStream) -> syn::Result<()> {
+    input.step(|cursor| {
+        let mut rest = *cursor;
+        while let Some((token_tree, next)) = rest.token_tree() {
+            match token_tree {
+                TokenTree::Punct(punct) if punct.as_char() == ';' => return Ok(((), next)),
+                TokenTree::Group(group) if group.delimiter() == Delimiter::Brace => {
+                    return Ok(((), next))
+                }
+                _ => rest = next,
+            }
+        }
+        Ok(((), rest))
+    })
+}
+
+fn unknown_section(name: &Ident) -> syn::Error {
+    unknown(name, "section", SECTIONS)
+}
+
+/// Error suggesting the closest valid name
+fn unknown(name: &Ident, what: &str, valid: &[&str]) -> syn::Error {
+    let name_string = name.to_string();
+
+    let closest = valid
+        .iter()
+        .map(|valid| (levenshtein(&name_string, valid), valid))
+        .filter(|(distance, _)| *distance <= 3)
+        .min_by_key(|(distance, _)| *distance);
+
+    let message = match closest {
+        Some((_, valid)) => {
+            format!("unknown {what} `{name_string}`, did you mean `{valid}`?")
+        }
+        None => format!(
+            "unknown {what} `{name_string}`, expected one of {}",
+            valid
+                .iter()
+                .map(|valid| format!("`{valid}`"))
+                .collect::<Vec<String>>()
+                .join(", ")
+        ),
+    };
+
+    syn::Error::new(name.span(), message)
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<char>>();
+    let mut previous = (0..=b.len()).collect::<Vec<usize>>();
+
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+#[derive(Default)]
+struct Errors(Option<syn::Error>);
+
+impl Errors {
+    fn push(&mut self, error: syn::Error) {
+        match &mut self.0 {
+            Some(errors) => errors.combine(error),
+            None => self.0 = Some(error),
+        }
+    }
+
+    fn finish(self) -> syn::Result<()> {
+        match self.0 {
+            Some(errors) => Err(errors),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_error(tokens: &str) -> String {
+        match syn::parse_str::<GenPlugin>(tokens) {
+            Ok(_) => panic!("`{tokens}` should not parse"),
+            Err(error) => error
+                .into_iter()
+                .map(|error| error.to_string())
+                .collect::<Vec<String>>()
+                .join("\n"),
+        }
+    }
+
+    #[test]
+    fn levenshtein_distances() {
+        assert_eq!(levenshtein("", ""), 0);
+        assert_eq!(levenshtein("systems", "systems"), 0);
+        assert_eq!(levenshtein("system", "systems"), 1);
+        assert_eq!(levenshtein("sytsems", "systems"), 2);
+        assert_eq!(levenshtein("", "runner"), 6);
+        assert_eq!(levenshtein("runner", ""), 6);
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn unknown_suggests_closest() {
+        let name = Ident::new("on_entre", Span::call_site());
+        assert_eq!(
+            unknown_section(&name).to_string(),
+            "unknown section `on_entre`, did you mean `on_enter`?",
+        );
+
+        let name = Ident::new("frobnicate", Span::call_site());
+        assert!(unknown_section(&name)
+            .to_string()
+            .starts_with("unknown section `frobnicate`, expected one of `plugins`"));
+    }
+
+    #[test]
+    fn duplicate_single_sections() {
+        assert_eq!(
+            parse_error("MyPlugin; runner(a); runner(b);"),
+            "duplicate `runner` section, an app can only have one runner\n\
+            first `runner` section here",
+        );
+        assert_eq!(
+            parse_error("MyPlugin; #[cfg(unix)] name(\"a\"); when(cfg(unix)) { name(\"b\"); }"),
+            "duplicate `name` section\nfirst `name` section here",
+        );
+    }
+
+    #[test]
+    fn test_has_is_an_invariant() {
+        let plugin = syn::parse_str::<GenPlugin>(
+            "MyPlugin; test_has(With<Character>, (CharacterController, CharacterSpeed));",
+        )
+        .unwrap_or_else(|error| panic!("{error}"));
+        assert!(matches!(
+            &plugin.sections[0].kind,
+            SectionKind::Invariants { severity: None, invariants, .. }
+                if matches!(invariants.as_slice(), [Invariant::Has(components)] if components.len() == 2)
+        ));
+
+        assert_eq!(
+            parse_error("MyPlugin; test_has(With<Character>, ());"),
+            "expected at least one component",
+        );
+    }
+
+    #[test]
+    fn derive_crate_path() {
+        let derive = syn::parse_str::<DerivePlugin>(
+            "#[gen_plugin(crate = ::my_gen_plugin; name(\"my\");)] struct MyPlugin;",
+        )
+        .unwrap_or_else(|error| panic!("{error}"));
+        assert_eq!(
+            derive.krate.to_token_stream().to_string(),
+            ":: my_gen_plugin",
+        );
+        assert_eq!(derive.plugin.sections.len(), 1);
+
+        let error = syn::parse_str::<DerivePlugin>(
+            "#[gen_plugin(crate = a)] #[gen_plugin(crate = b)] struct MyPlugin;",
+        )
+        .err()
+        .expect("two crate paths should not parse");
+        assert_eq!(error.to_string(), "duplicate `crate` path");
+    }
+
+    #[test]
+    fn settings_extension() {
+        assert_eq!(
+            parse_error("MyPlugin; settings(MySettings = \"settings/camera.ron\");"),
+            "settings files are loaded by their `.settings.ron` extension, \
+            e.g. `\"settings/my.settings.ron\"`",
+        );
+        syn::parse_str::<GenPlugin>(
+            "MyPlugin; settings(MySettings = \"settings/camera.settings.ron\", Other = PATH);",
+        )
+        .unwrap_or_else(|error| panic!("{error}"));
+    }
+
+    #[test]
+    fn single_sections_under_different_cfg() {
+        syn::parse_str::<GenPlugin>(
+            "MyPlugin; #[cfg(unix)] runner(a); #[cfg(not(unix))] runner(b);",
+        )
+        .unwrap_or_else(|error| panic!("{error}"));
+        syn::parse_str::<GenPlugin>(
+            "MyPlugin;
+            when(cfg(unix)) { runner(a); self_test(frames = 1); }
+            when(cfg(not(unix))) { runner(b); self_test(frames = 1); }",
+        )
+        .unwrap_or_else(|error| panic!("{error}"));
+    }
+}